@@ -1,15 +1,38 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{Report, Result};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use crate::{
+    audit::{AuditEntry, AuditLog},
+    backup::{Backup, BackupOptions},
+    executor::{self, Job, JobStatus},
+    filter::RepoFilter,
     fuzzy::FuzzyMatcher,
-    github::GitHubClient,
-    state::{AppMode, AppState, SortMode, StatusLevel},
-    tui::{self, Event, EventHandler},
+    github::{
+        cache::{PageCache, RepoCache},
+        client::RepoListing,
+        types::{Repository, TokenInfo},
+        GitHubClient, Host,
+    },
+    keymap::{KeyAction, Keymap},
+    plan::{self, PlanEntry},
+    safety::SafetyConfig,
+    state::{AppMode, AppState, DetailsState, JobState, ReadmeState, RepoAction, StatusLevel},
+    tui::{self, theme::Theme, Event, EventHandler},
 };
 
+/// Detail fetches allowed in flight at once, so staging a large plan
+/// doesn't trip GitHub's secondary rate limits.
+const MAX_DETAIL_FETCHES: usize = 4;
+
+/// Rows the help overlay scrolls by on PageUp/PageDown.
+const HELP_PAGE: i32 = 10;
+
 pub enum Action {
     None,
     Quit,
@@ -17,19 +40,83 @@ pub enum Action {
     ExecuteDeletion,
 }
 
+pub struct AppOptions {
+    pub dry_run: bool,
+    pub backup: Option<BackupOptions>,
+    pub plan_path: Option<PathBuf>,
+    pub concurrency: usize,
+    pub safety: SafetyConfig,
+    pub filter: RepoFilter,
+    pub keymap: Keymap,
+    pub theme: Theme,
+    pub profile: String,
+    pub token: TokenInfo,
+    pub host: Host,
+}
+
 pub struct App {
     pub state: AppState,
     fuzzy: FuzzyMatcher,
     client: GitHubClient,
+    backup: Option<Backup>,
+    audit: AuditLog,
+    audit_error: Option<Report>,
+    plan_path: Option<PathBuf>,
+    pending_plan: Option<Vec<PlanEntry>>,
+    concurrency: usize,
+    cancel: Arc<AtomicBool>,
+    /// Where the repository list is cached between runs, if anywhere.
+    cache_path: Option<PathBuf>,
+    /// Listing pages from the last refresh, revalidated by the next one.
+    page_cache: PageCache,
+    /// Changes applied since the refresh in progress started, whose listing
+    /// may predate them.
+    applied_during_refresh: Vec<(String, RepoAction)>,
 }
 
 impl App {
-    pub async fn new(token: &str, dry_run: bool) -> Result<Self> {
-        let client = GitHubClient::new(token).await?;
+    pub async fn new(token: &str, options: AppOptions) -> Result<Self> {
+        let client = GitHubClient::new(token, &options.host).await?;
+        // A plan path that doesn't exist yet is just where `w` will write to.
+        let pending_plan = match &options.plan_path {
+            Some(path) if path.exists() => Some(plan::load(path)?),
+            _ => None,
+        };
+        let mut state = AppState::new(options.dry_run);
+        state.rate_limit = client.rate_limit().clone();
+        state.safety = options.safety;
+        state.set_repo_filter(options.filter);
+        state.keymap = options.keymap;
+        state.theme = options.theme;
+        state.profile = options.profile;
+        state.host = (!options.host.is_github_com()).then(|| options.host.to_string());
+        state.token_warning = options.token.deletion_blocker();
+
+        let cache_path = RepoCache::path(&options.host, &options.token.login).ok();
+        state.login = Some(options.token.login);
+        let cache = cache_path.as_deref().and_then(RepoCache::load);
+        let page_cache = match cache {
+            Some(cache) => {
+                state.repos_stale = true;
+                state.repositories = cache.repos;
+                cache.pages
+            }
+            None => PageCache::default(),
+        };
         Ok(Self {
-            state: AppState::new(dry_run),
+            state,
             fuzzy: FuzzyMatcher::new(),
             client,
+            backup: options.backup.map(|backup| Backup::new(token, backup)),
+            audit: AuditLog::new()?,
+            audit_error: None,
+            plan_path: options.plan_path,
+            pending_plan,
+            concurrency: options.concurrency,
+            cancel: Arc::new(AtomicBool::new(false)),
+            cache_path,
+            page_cache,
+            applied_during_refresh: Vec::new(),
         })
     }
 
@@ -37,7 +124,8 @@ impl App {
         let mut terminal = tui::terminal::init()?;
         let mut events = EventHandler::new(Duration::from_millis(100));
 
-        self.load_repos().await;
+        self.update_filtered();
+        self.start_loading(&events);
 
         loop {
             terminal.draw(|frame| tui::ui::render(frame, &self.state))?;
@@ -47,136 +135,427 @@ impl App {
                     let action = self.handle_key(key);
                     match action {
                         Action::Quit => break,
-                        Action::Refresh => {
-                            self.state.loading = true;
-                            self.load_repos().await;
-                        }
-                        Action::ExecuteDeletion => {
-                            self.execute_deletion(&mut terminal).await;
-                        }
+                        Action::Refresh if self.state.loading => {}
+                        Action::Refresh => self.start_loading(&events),
+                        Action::ExecuteDeletion => self.start_jobs(&events),
                         Action::None => {}
                     }
                 }
-                Event::Tick => {
-                    self.state.clear_status();
-                }
+                Event::Tick => self.state.tick(),
                 Event::Resize => {}
+                Event::Job(slot, status) => self.handle_job_update(slot, status),
+                Event::JobsFinished => self.finish_jobs(),
+                Event::ReposPage(repos) => self.add_page(repos),
+                Event::Repos(result) => self.finish_loading(result),
+                Event::Details(name, result) => {
+                    let details = match result {
+                        Ok(details) => DetailsState::Loaded(details),
+                        Err(e) => DetailsState::Failed(e),
+                    };
+                    self.state.details.insert(name, details);
+                }
+                Event::Readme(name, result) => {
+                    let readme = match result {
+                        Ok(readme) => ReadmeState::Loaded(readme),
+                        Err(e) => ReadmeState::Failed(e),
+                    };
+                    self.state.readmes.insert(name, readme);
+                }
             }
+
+            self.fetch_details(&events);
         }
 
         tui::terminal::restore()?;
         Ok(())
     }
 
-    async fn load_repos(&mut self) {
-        self.state.loading = true;
-        match self.client.list_repos().await {
-            Ok(repos) => {
-                self.state.repositories = repos;
-                self.update_filtered();
-                self.state.loading = false;
-                self.state.set_status(
-                    format!("Loaded {} repositories", self.state.repositories.len()),
-                    StatusLevel::Success,
-                );
+    /// Start loading details and the README for the selected repo (if the
+    /// detail pane is open), details for the selected staged repo, and,
+    /// once the confirmation dialog is open, details for every staged
+    /// deletion, whose open pull requests and watchers feed the risk flags.
+    /// At most `MAX_DETAIL_FETCHES` run at once; the rest are picked up on
+    /// later events.
+    fn fetch_details(&mut self, events: &EventHandler) {
+        let selected = self
+            .state
+            .selected_repo()
+            .filter(|_| self.state.show_details)
+            .map(|repo| repo.full_name.clone());
+        let mut wanted: Vec<String> = selected.iter().cloned().collect();
+        let staged = self.state.staged_repos_sorted();
+        match self.state.mode {
+            AppMode::Staging => {
+                wanted.extend(staged.get(self.state.staged_selected_index).cloned());
             }
-            Err(e) => {
-                self.state.loading = false;
+            AppMode::ConfirmDeletion => wanted.extend(
+                staged
+                    .into_iter()
+                    .filter(|name| self.state.staged_for_deletion[name] == RepoAction::Delete),
+            ),
+            _ => {}
+        }
+
+        let mut in_flight = self
+            .state
+            .details
+            .values()
+            .filter(|d| matches!(d, DetailsState::Loading))
+            .count()
+            + self
+                .state
+                .readmes
+                .values()
+                .filter(|r| matches!(r, ReadmeState::Loading))
+                .count();
+
+        if let Some(name) = selected {
+            if in_flight < MAX_DETAIL_FETCHES && !self.state.readmes.contains_key(&name) {
                 self.state
-                    .set_status(format!("Failed to load repos: {}", e), StatusLevel::Error);
+                    .readmes
+                    .insert(name.clone(), ReadmeState::Loading);
+                in_flight += 1;
+
+                let client = self.client.clone();
+                let tx = events.sender();
+                tokio::spawn(async move {
+                    let result = client.readme(&name).await.map_err(|e| format!("{:#}", e));
+                    let _ = tx.send(Event::Readme(name, result));
+                });
             }
         }
-    }
-
-    fn update_filtered(&mut self) {
-        let mut indices = self
-            .fuzzy
-            .filter(&self.state.repositories, &self.state.search_query);
 
-        // Apply filters
-        indices.retain(|&idx| {
-            let repo = &self.state.repositories[idx];
-            if self.state.filter_private && !repo.private {
-                return false;
+        for name in wanted {
+            if in_flight >= MAX_DETAIL_FETCHES {
+                break;
             }
-            if self.state.filter_forks && !repo.fork {
-                return false;
+            if self.state.details.contains_key(&name) {
+                continue;
             }
-            true
+            self.state
+                .details
+                .insert(name.clone(), DetailsState::Loading);
+            in_flight += 1;
+
+            let client = self.client.clone();
+            let tx = events.sender();
+            tokio::spawn(async move {
+                let result = client
+                    .repo_details(&name)
+                    .await
+                    .map_err(|e| format!("{:#}", e));
+                let _ = tx.send(Event::Details(name, result));
+            });
+        }
+    }
+
+    /// Refresh the repository list in the background. Until it finishes,
+    /// the current (possibly cached) list stays usable; an empty list is
+    /// filled page by page instead, so it can be searched and staged before
+    /// the last page arrives.
+    fn start_loading(&mut self, events: &EventHandler) {
+        self.state.loading = true;
+        self.state.loaded_count = 0;
+        self.state.repos_stale = !self.state.repositories.is_empty();
+        self.applied_during_refresh.clear();
+        let client = self.client.clone();
+        let pages = self.page_cache.clone();
+        let tx = events.sender();
+        tokio::spawn(async move {
+            let page_tx = tx.clone();
+            let mut on_page = |repos: &[Repository]| {
+                let _ = page_tx.send(Event::ReposPage(repos.to_vec()));
+            };
+            let result = client.list_all_repos(&pages, &mut on_page).await;
+            let _ = tx.send(Event::Repos(result.map_err(|e| format!("{:#}", e))));
         });
+    }
 
-        // Apply sorting
-        match self.state.sort_mode {
-            SortMode::LastUpdated => {
-                indices.sort_by(|&a, &b| {
-                    let repo_a = &self.state.repositories[a];
-                    let repo_b = &self.state.repositories[b];
-                    repo_b.updated_at.cmp(&repo_a.updated_at)
-                });
+    fn add_page(&mut self, mut repos: Vec<Repository>) {
+        self.state.loaded_count += repos.len();
+        apply_changes(&self.applied_during_refresh, &mut repos);
+        if !self.state.repos_stale {
+            // A sorted or filtered page can land above the cursor.
+            let selected = self.state.selected_repo().map(|r| r.full_name.clone());
+            self.state.repositories.extend(repos);
+            self.update_filtered();
+            if let Some(name) = selected {
+                self.state.select_repo(&name);
             }
-            SortMode::Created => {
-                indices.sort_by(|&a, &b| {
-                    let repo_a = &self.state.repositories[a];
-                    let repo_b = &self.state.repositories[b];
-                    repo_b.created_at.cmp(&repo_a.created_at)
-                });
+        }
+    }
+
+    fn finish_loading(&mut self, result: Result<RepoListing, String>) {
+        self.state.loading = false;
+        let mut listing = match result {
+            Ok(listing) => listing,
+            Err(e) => {
+                let message = if self.state.repos_stale {
+                    format!("Failed to refresh repos, showing cached list: {}", e)
+                } else if !self.state.repositories.is_empty() {
+                    format!(
+                        "Failed to load repos, showing the first {}: {}",
+                        self.state.repositories.len(),
+                        e
+                    )
+                } else {
+                    format!("Failed to load repos: {}", e)
+                };
+                self.state.set_status(message, StatusLevel::Error);
+                return;
             }
-            SortMode::Name => {
-                indices.sort_by(|&a, &b| {
-                    let repo_a = &self.state.repositories[a];
-                    let repo_b = &self.state.repositories[b];
-                    repo_a
-                        .full_name
-                        .to_lowercase()
-                        .cmp(&repo_b.full_name.to_lowercase())
-                });
+        };
+
+        apply_changes(&self.applied_during_refresh, &mut listing.repos);
+        self.applied_during_refresh.clear();
+        if let Some(path) = &self.cache_path {
+            let cache = RepoCache {
+                fetched_at: Some(chrono::Utc::now()),
+                repos: listing.repos.clone(),
+                pages: listing.pages.clone(),
+            };
+            // A cache that can't be written only makes the next start slower.
+            let _ = cache.save(path);
+        }
+
+        // Keep the cursor on the same repo when the list is replaced.
+        let selected = self.state.selected_repo().map(|r| r.full_name.clone());
+        self.state.repositories = listing.repos;
+        self.state.repos_stale = false;
+        self.page_cache = listing.pages;
+        // Details and READMEs outlive a refresh; only failed fetches are
+        // retried, and repos that are gone are dropped.
+        let listed: HashSet<&str> = self
+            .state
+            .repositories
+            .iter()
+            .map(|r| r.full_name.as_str())
+            .collect();
+        self.state.details.retain(|name, details| {
+            listed.contains(name.as_str()) && !matches!(details, DetailsState::Failed(_))
+        });
+        self.state.readmes.retain(|name, readme| {
+            listed.contains(name.as_str()) && !matches!(readme, ReadmeState::Failed(_))
+        });
+        if let Some(owner) = &self.state.owner_filter {
+            if !self.state.owners().contains(owner) {
+                self.state.owner_filter = None;
             }
-            SortMode::Stars => {
-                indices.sort_by(|&a, &b| {
-                    let repo_a = &self.state.repositories[a];
-                    let repo_b = &self.state.repositories[b];
-                    repo_b.stargazers_count.cmp(&repo_a.stargazers_count)
-                });
+        }
+        self.update_filtered();
+        if let Some(name) = selected {
+            self.state.select_repo(&name);
+        }
+
+        let loaded = format!("Loaded {} repositories", self.state.repositories.len());
+        match listing.org_errors.first() {
+            None => self.state.set_status(loaded, StatusLevel::Success),
+            Some(err) => self.state.set_status(
+                format!(
+                    "{} ({} org(s) failed: {})",
+                    loaded,
+                    listing.org_errors.len(),
+                    err
+                ),
+                StatusLevel::Warning,
+            ),
+        }
+        if let Some(entries) = self.pending_plan.take() {
+            self.stage_plan(entries);
+        }
+    }
+
+    fn stage_plan(&mut self, entries: Vec<PlanEntry>) {
+        let before = self.state.staged_for_deletion.clone();
+        let mut staged = 0;
+        let mut missing = 0;
+        let mut protected = 0;
+        for entry in entries {
+            let name = entry.repo.full_name;
+            if self.state.safety.is_protected(&name) {
+                protected += 1;
+            } else if self.state.repositories.iter().any(|r| r.full_name == name) {
+                self.state.staged_for_deletion.insert(name, entry.action);
+                staged += 1;
+            } else {
+                missing += 1;
             }
         }
 
+        self.state
+            .record_staging(format!("stage {} repos from plan", staged), before);
+        let mut skipped = Vec::new();
+        if missing > 0 {
+            skipped.push(format!("{} no longer exist", missing));
+        }
+        if protected > 0 {
+            skipped.push(format!("{} protected", protected));
+        }
+        if skipped.is_empty() {
+            self.state.set_status(
+                format!("Staged {} repos from plan", staged),
+                StatusLevel::Info,
+            );
+        } else {
+            self.state.set_status(
+                format!("Staged {} repos from plan, {}", staged, skipped.join(", ")),
+                StatusLevel::Warning,
+            );
+        }
+    }
+
+    fn write_plan(&mut self) {
+        let entries: Vec<PlanEntry> = self
+            .state
+            .staged_repos_sorted()
+            .into_iter()
+            .filter_map(|name| {
+                let repo = self
+                    .state
+                    .repositories
+                    .iter()
+                    .find(|r| r.full_name == name)?;
+                Some(PlanEntry {
+                    repo: repo.clone(),
+                    action: self.state.staged_for_deletion[&name],
+                })
+            })
+            .collect();
+
+        let path = self.plan_path.get_or_insert_with(|| {
+            PathBuf::from(format!(
+                "ghune-plan-{}.json",
+                chrono::Local::now().format("%Y%m%d-%H%M%S")
+            ))
+        });
+
+        match plan::save(path, &entries) {
+            Ok(()) => self.state.set_status(
+                format!("Wrote {} repos to {}", entries.len(), path.display()),
+                StatusLevel::Success,
+            ),
+            Err(e) => self
+                .state
+                .set_status(format!("Failed to write plan: {:#}", e), StatusLevel::Error),
+        }
+    }
+
+    fn update_filtered(&mut self) {
+        let indices = self
+            .state
+            .repo_filter()
+            .apply(&mut self.fuzzy, &self.state.repositories);
+
         self.state.filtered_indices = indices;
         if self.state.selected_index >= self.state.filtered_indices.len() {
             self.state.selected_index = 0;
         }
+        if self.state.visual_range().is_none() {
+            self.state.visual_anchor = None;
+        }
     }
 
     fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let action = self.state.keymap.action(self.state.mode, key);
+        if self.state.show_help {
+            match (action, key.code) {
+                (Some(KeyAction::Help | KeyAction::Back), _) | (_, KeyCode::Esc) => {
+                    self.state.show_help = false;
+                }
+                (Some(KeyAction::Down), _) | (_, KeyCode::Down | KeyCode::Char('j')) => {
+                    self.state.scroll_help(1);
+                }
+                (Some(KeyAction::Up), _) | (_, KeyCode::Up | KeyCode::Char('k')) => {
+                    self.state.scroll_help(-1);
+                }
+                (_, KeyCode::PageDown) => self.state.scroll_help(HELP_PAGE),
+                (_, KeyCode::PageUp) => self.state.scroll_help(-HELP_PAGE),
+                (Some(KeyAction::Top), _) | (_, KeyCode::Home) => self.state.help_scroll = 0,
+                (Some(KeyAction::Bottom), _) | (_, KeyCode::End) => {
+                    self.state.scroll_help(i32::MAX / 2)
+                }
+                _ => {}
+            }
+            return Action::None;
+        }
+        if let Some(change) = self.state.pending_bulk.take() {
+            // Bulk changes are confirmed with the same key that
+            // acknowledges risks in the confirmation dialog.
+            if self.state.keymap.action(AppMode::ConfirmDeletion, key)
+                == Some(KeyAction::Acknowledge)
+            {
+                self.state.apply_bulk(change);
+            } else {
+                self.state
+                    .set_status("Cancelled".to_string(), StatusLevel::Info);
+            }
+            return Action::None;
+        }
+        if action == Some(KeyAction::Help) {
+            self.state.show_help = true;
+            self.state.help_scroll = 0;
+            return Action::None;
+        }
+
         match self.state.mode {
             AppMode::Search => self.handle_search_key(key),
             AppMode::Staging => self.handle_staging_key(key),
             AppMode::ConfirmDeletion => self.handle_confirm_key(key),
-            AppMode::Deleting => Action::None,
+            AppMode::Deleting => self.handle_deleting_key(key),
+            AppMode::History => self.handle_history_key(key),
         }
     }
 
     fn handle_search_key(&mut self, key: KeyEvent) -> Action {
-        match (key.code, key.modifiers) {
-            (KeyCode::Char('c'), KeyModifiers::CONTROL)
-            | (KeyCode::Char('q'), KeyModifiers::NONE) => Action::Quit,
+        let Some(action) = self.state.keymap.action(AppMode::Search, key) else {
+            // Unbound printable keys go into the search query.
+            if let (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) =
+                (key.code, key.modifiers)
+            {
+                self.state.search_query.push(c);
+                self.update_filtered();
+            }
+            return Action::None;
+        };
 
-            (KeyCode::Char('j'), KeyModifiers::CONTROL) | (KeyCode::Down, _) => {
-                self.state.move_selection(1);
-                Action::None
+        match action {
+            KeyAction::Quit => return Action::Quit,
+
+            KeyAction::Down => self.state.move_selection(1),
+
+            KeyAction::Up => self.state.move_selection(-1),
+
+            KeyAction::Top => self.state.select_edge(false),
+
+            KeyAction::Bottom => self.state.select_edge(true),
+
+            KeyAction::Stage if self.state.visual_anchor.is_some() => {
+                self.state.stage_visual(false)
             }
 
-            (KeyCode::Char('k'), KeyModifiers::CONTROL) | (KeyCode::Up, _) => {
-                self.state.move_selection(-1);
-                Action::None
+            KeyAction::StageArchive if self.state.visual_anchor.is_some() => {
+                self.state.stage_visual(true)
             }
 
-            (KeyCode::Enter, KeyModifiers::NONE) | (KeyCode::Char(' '), KeyModifiers::NONE) => {
+            KeyAction::Stage => {
                 self.state.toggle_stage();
                 self.state.move_selection(1);
-                Action::None
             }
 
-            (KeyCode::Tab, KeyModifiers::NONE) => {
+            KeyAction::StageArchive => {
+                self.state.toggle_stage_archive();
+                self.state.move_selection(1);
+            }
+
+            KeyAction::StageVisible => self.state.stage_visible(),
+
+            KeyAction::UnstageVisible => self.state.unstage_visible(),
+
+            KeyAction::InvertVisible => self.state.invert_visible(),
+
+            KeyAction::VisualRange => self.state.toggle_visual(),
+
+            KeyAction::FocusStaging => {
                 if !self.state.staged_for_deletion.is_empty() {
                     self.state.mode = AppMode::Staging;
                     self.state.staged_selected_index = 0;
@@ -186,12 +565,15 @@ impl App {
                         StatusLevel::Warning,
                     );
                 }
-                Action::None
             }
 
-            (KeyCode::Char('r'), KeyModifiers::CONTROL) => Action::Refresh,
+            KeyAction::Undo => self.state.undo_staging(),
+
+            KeyAction::Redo => self.state.redo_staging(),
 
-            (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+            KeyAction::Refresh => return Action::Refresh,
+
+            KeyAction::TogglePrivate => {
                 self.state.filter_private = !self.state.filter_private;
                 let status = if self.state.filter_private {
                     "Filter: private repos only"
@@ -200,10 +582,9 @@ impl App {
                 };
                 self.state.set_status(status.to_string(), StatusLevel::Info);
                 self.update_filtered();
-                Action::None
             }
 
-            (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
+            KeyAction::ToggleForks => {
                 self.state.filter_forks = !self.state.filter_forks;
                 let status = if self.state.filter_forks {
                     "Filter: forks only"
@@ -212,189 +593,550 @@ impl App {
                 };
                 self.state.set_status(status.to_string(), StatusLevel::Info);
                 self.update_filtered();
-                Action::None
             }
 
-            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
+            KeyAction::CycleOwner => {
+                self.state.cycle_owner_filter();
+                let status = match &self.state.owner_filter {
+                    Some(owner) => format!("Owner: {}", owner),
+                    None => "Owner: showing all owners".to_string(),
+                };
+                self.state.set_status(status, StatusLevel::Info);
+                self.update_filtered();
+            }
+
+            KeyAction::OpenHistory => self.open_history(),
+
+            KeyAction::ToggleDetails => self.state.show_details = !self.state.show_details,
+
+            KeyAction::CycleSort => {
                 self.state.sort_mode = self.state.sort_mode.next();
                 self.state.set_status(
                     format!("Sort: {}", self.state.sort_mode.label()),
                     StatusLevel::Info,
                 );
                 self.update_filtered();
-                Action::None
             }
 
-            (KeyCode::Esc, _) => {
-                self.state.search_query.clear();
-                self.update_filtered();
-                Action::None
+            KeyAction::ClearQuery if self.state.visual_anchor.is_some() => {
+                self.state.toggle_visual()
             }
 
-            (KeyCode::Backspace, _) => {
-                self.state.search_query.pop();
+            KeyAction::ClearQuery => {
+                self.state.search_query.clear();
                 self.update_filtered();
-                Action::None
             }
 
-            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
-                self.state.search_query.push(c);
+            KeyAction::DeleteChar => {
+                self.state.search_query.pop();
                 self.update_filtered();
-                Action::None
             }
 
-            _ => Action::None,
+            _ => {}
         }
+        Action::None
     }
 
     fn handle_staging_key(&mut self, key: KeyEvent) -> Action {
-        match (key.code, key.modifiers) {
-            (KeyCode::Char('c'), KeyModifiers::CONTROL)
-            | (KeyCode::Char('q'), KeyModifiers::NONE) => Action::Quit,
+        let Some(action) = self.state.keymap.action(AppMode::Staging, key) else {
+            return Action::None;
+        };
 
-            (KeyCode::Char('j'), KeyModifiers::CONTROL) | (KeyCode::Down, _) => {
-                self.state.move_selection(1);
-                Action::None
+        match action {
+            KeyAction::Quit => return Action::Quit,
+
+            KeyAction::Down => self.state.move_selection(1),
+
+            KeyAction::Up => self.state.move_selection(-1),
+
+            KeyAction::Top => self.state.select_edge(false),
+
+            KeyAction::Bottom => self.state.select_edge(true),
+
+            KeyAction::Unstage => {
+                self.state.unstage_selected();
+                if self.state.staged_for_deletion.is_empty() {
+                    self.state.mode = AppMode::Search;
+                }
             }
 
-            (KeyCode::Char('k'), KeyModifiers::CONTROL) | (KeyCode::Up, _) => {
-                self.state.move_selection(-1);
-                Action::None
+            KeyAction::UnstageAll => {
+                self.state.unstage_all();
+                self.state.mode = AppMode::Search;
             }
 
-            (KeyCode::Backspace, _)
-            | (KeyCode::Delete, _)
-            | (KeyCode::Char(' '), KeyModifiers::NONE) => {
-                self.state.unstage_selected();
+            KeyAction::CycleAction => self.state.cycle_selected_action(),
+
+            KeyAction::Undo | KeyAction::Redo => {
+                if action == KeyAction::Undo {
+                    self.state.undo_staging();
+                } else {
+                    self.state.redo_staging();
+                }
                 if self.state.staged_for_deletion.is_empty() {
                     self.state.mode = AppMode::Search;
                 }
-                Action::None
             }
 
-            (KeyCode::Enter, KeyModifiers::NONE) => {
+            KeyAction::WritePlan => self.write_plan(),
+
+            KeyAction::Apply => {
                 if !self.state.staged_for_deletion.is_empty() {
                     self.state.confirmation_input.clear();
+                    self.state.acknowledged.clear();
                     self.state.mode = AppMode::ConfirmDeletion;
                 } else {
                     self.state.mode = AppMode::Search;
                 }
-                Action::None
             }
 
-            (KeyCode::Tab, KeyModifiers::NONE) | (KeyCode::Esc, _) => {
+            KeyAction::Back => self.state.mode = AppMode::Search,
+
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn handle_deleting_key(&mut self, key: KeyEvent) -> Action {
+        match self.state.keymap.action(AppMode::Deleting, key) {
+            Some(KeyAction::Back | KeyAction::CancelJobs) if self.state.jobs_done => {
+                self.state.jobs.clear();
+                self.state.jobs_done = false;
                 self.state.mode = AppMode::Search;
-                Action::None
             }
+            Some(KeyAction::CancelJobs) if !self.state.cancelling => {
+                self.cancel.store(true, Ordering::Relaxed);
+                self.state.cancelling = true;
+                self.state.set_status(
+                    "Cancelling: waiting for in-flight requests...".to_string(),
+                    StatusLevel::Warning,
+                );
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn handle_history_key(&mut self, key: KeyEvent) -> Action {
+        match self.state.keymap.action(AppMode::History, key) {
+            Some(KeyAction::Quit) => return Action::Quit,
+            Some(KeyAction::Down) => self.state.move_selection(1),
+            Some(KeyAction::Up) => self.state.move_selection(-1),
+            Some(KeyAction::Top) => self.state.select_edge(false),
+            Some(KeyAction::Bottom) => self.state.select_edge(true),
+            Some(KeyAction::Back) => self.state.mode = AppMode::Search,
+            _ => {}
+        }
+        Action::None
+    }
 
-            _ => Action::None,
+    fn open_history(&mut self) {
+        match self.audit.read_all() {
+            Ok(mut entries) => {
+                entries.reverse();
+                self.state.history = entries;
+                self.state.history_selected_index = 0;
+                self.state.mode = AppMode::History;
+            }
+            Err(e) => self.state.set_status(
+                format!("Failed to read history: {:#}", e),
+                StatusLevel::Error,
+            ),
         }
     }
 
     fn handle_confirm_key(&mut self, key: KeyEvent) -> Action {
-        match (key.code, key.modifiers) {
-            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Action::Quit,
+        let Some(action) = self.state.keymap.action(AppMode::ConfirmDeletion, key) else {
+            if let KeyCode::Char(c @ '0'..='9') = key.code {
+                self.state.confirmation_input.push(c);
+            }
+            return Action::None;
+        };
 
-            (KeyCode::Esc, _) => {
+        match action {
+            KeyAction::Quit => return Action::Quit,
+
+            KeyAction::Back => {
                 self.state.confirmation_input.clear();
                 self.state.mode = AppMode::Staging;
-                Action::None
             }
 
-            (KeyCode::Enter, KeyModifiers::NONE) => {
+            // Risky deletions are acknowledged one at a time, once their
+            // details have loaded.
+            KeyAction::Acknowledge if self.state.pending_risk_checks() == 0 => {
+                if let Some((name, _)) = self.state.next_unacknowledged() {
+                    self.state.acknowledged.insert(name);
+                }
+            }
+
+            KeyAction::Apply => {
                 let expected = self.state.staged_for_deletion.len().to_string();
-                if self.state.confirmation_input == expected {
+                let pending = self.state.pending_risk_checks();
+                if pending > 0 {
+                    self.state.set_status(
+                        format!("Still checking {} repos for risks", pending),
+                        StatusLevel::Warning,
+                    );
+                } else if self.state.next_unacknowledged().is_some() {
+                    let key = self
+                        .state
+                        .keymap
+                        .hint(AppMode::ConfirmDeletion, KeyAction::Acknowledge)
+                        .unwrap_or_default();
+                    self.state.set_status(
+                        format!("Press {} to acknowledge each risky repo", key),
+                        StatusLevel::Warning,
+                    );
+                } else if self.state.confirmation_input == expected {
                     self.state.confirmation_input.clear();
-                    Action::ExecuteDeletion
+                    return Action::ExecuteDeletion;
                 } else {
                     self.state.set_status(
                         format!("Type '{}' to confirm deletion", expected),
                         StatusLevel::Warning,
                     );
-                    Action::None
                 }
             }
 
-            (KeyCode::Backspace, _) => {
+            KeyAction::DeleteChar => {
                 self.state.confirmation_input.pop();
-                Action::None
             }
 
-            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
-                if c.is_ascii_digit() {
-                    self.state.confirmation_input.push(c);
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn start_jobs(&mut self, events: &EventHandler) {
+        let mut jobs = Vec::new();
+        self.state.jobs.clear();
+        for name in self.state.staged_repos_sorted() {
+            let action = self.state.staged_for_deletion[&name];
+            let repo = self.state.repositories.iter().find(|r| r.full_name == name);
+            let status = match (repo, self.state.token_warning) {
+                // A token that can't delete fails deletions up front, even in
+                // the dry run it forces.
+                (Some(_), Some(blocker)) if action == RepoAction::Delete => {
+                    JobStatus::Failed(blocker.to_string())
+                }
+                (Some(repo), _) => {
+                    jobs.push(Job {
+                        repo: repo.clone(),
+                        action,
+                    });
+                    JobStatus::Queued
+                }
+                (None, _) => JobStatus::Failed("no longer in the repository list".to_string()),
+            };
+            // Audit these like jobs that fail while running.
+            if let JobStatus::Failed(error) = &status {
+                if let Err(e) = self.record_audit(&name, action, Some(error.clone())) {
+                    self.audit_error.get_or_insert(e);
                 }
-                Action::None
             }
-
-            _ => Action::None,
+            self.state.jobs.push(JobState {
+                repo: name,
+                action,
+                status,
+            });
         }
-    }
 
-    async fn execute_deletion(&mut self, terminal: &mut tui::terminal::Terminal) {
+        // Jobs that can't run were recorded as failed above, so map each
+        // spawned job back to its slot in `state.jobs`.
+        let slots: Vec<usize> = self
+            .state
+            .jobs
+            .iter()
+            .enumerate()
+            .filter(|(_, job)| job.status == JobStatus::Queued)
+            .map(|(slot, _)| slot)
+            .collect();
+
         self.state.mode = AppMode::Deleting;
-        let repos_to_delete: Vec<String> = self.state.staged_for_deletion.iter().cloned().collect();
-        let total = repos_to_delete.len();
+        self.state.cancelling = false;
+        self.cancel.store(false, Ordering::Relaxed);
 
-        let mut deleted = Vec::new();
-        let mut failed = Vec::new();
+        let client = self.client.clone();
+        let backup = self.backup.clone();
+        let cancel = Arc::clone(&self.cancel);
+        let concurrency = self.concurrency;
+        let dry_run = self.state.dry_run;
+        let tx = events.sender();
 
-        for (i, repo_name) in repos_to_delete.iter().enumerate() {
-            self.state.deletion_progress = Some((i + 1, total));
-            self.state.deleting_repo = Some(repo_name.clone());
+        tokio::spawn(async move {
+            executor::run(
+                &client,
+                backup.as_ref(),
+                &jobs,
+                concurrency,
+                dry_run,
+                &cancel,
+                |index, status| {
+                    let _ = tx.send(Event::Job(slots[index], status));
+                },
+            )
+            .await;
+            let _ = tx.send(Event::JobsFinished);
+        });
+    }
 
-            // Redraw to show progress
-            let _ = terminal.draw(|frame| tui::ui::render(frame, &self.state));
+    fn handle_job_update(&mut self, slot: usize, status: JobStatus) {
+        let Some(job) = self.state.jobs.get_mut(slot) else {
+            return;
+        };
+        job.status = status.clone();
+        let (name, action) = (job.repo.clone(), job.action);
 
-            if self.state.dry_run {
-                // Animate spinner during dry run
-                for _ in 0..5 {
-                    self.state.advance_spinner();
-                    let _ = terminal.draw(|frame| tui::ui::render(frame, &self.state));
-                    tokio::time::sleep(Duration::from_millis(80)).await;
-                }
-                deleted.push(repo_name.clone());
-            } else {
-                // Start spinner animation in background
-                let delete_result = self.client.delete_repo(repo_name).await;
-                match delete_result {
-                    Ok(()) => {
-                        deleted.push(repo_name.clone());
-                    }
-                    Err(e) => {
-                        failed.push((repo_name.clone(), e.to_string()));
-                    }
-                }
-            }
+        let error = match &status {
+            JobStatus::Succeeded => None,
+            JobStatus::Failed(e) => Some(e.clone()),
+            _ => return,
+        };
+        if let Err(e) = self.record_audit(&name, action, error.clone()) {
+            self.audit_error.get_or_insert(e);
         }
-
-        for name in &deleted {
-            self.state.staged_for_deletion.remove(name);
-            self.state.repositories.retain(|r| &r.full_name != name);
+        if error.is_some() {
+            return;
         }
 
+        self.state.staged_for_deletion.remove(&name);
+        let change = (name, action);
+        apply_changes(std::slice::from_ref(&change), &mut self.state.repositories);
+        if self.state.loading && !self.state.dry_run {
+            self.applied_during_refresh.push(change);
+        }
         self.update_filtered();
-        self.state.deletion_progress = None;
-        self.state.deleting_repo = None;
-        self.state.mode = AppMode::Search;
+    }
 
-        if failed.is_empty() {
-            let msg = if self.state.dry_run {
-                format!("[DRY RUN] Would have deleted {} repos", deleted.len())
-            } else {
-                format!("Successfully deleted {} repos", deleted.len())
-            };
-            self.state.set_status(msg, StatusLevel::Success);
-        } else {
-            self.state.set_status(
+    fn finish_jobs(&mut self) {
+        let succeeded: Vec<(String, RepoAction)> = self
+            .state
+            .jobs
+            .iter()
+            .filter(|job| job.status == JobStatus::Succeeded)
+            .map(|job| (job.repo.clone(), job.action))
+            .collect();
+        let failed: Vec<&str> = self
+            .state
+            .jobs
+            .iter()
+            .filter_map(|job| match &job.status {
+                JobStatus::Failed(e) => Some(e.as_str()),
+                _ => None,
+            })
+            .collect();
+        let cancelled = self
+            .state
+            .jobs
+            .iter()
+            .filter(|job| job.status == JobStatus::Cancelled)
+            .count();
+
+        let summary = summarize_actions(&succeeded);
+        let (msg, level) = if !failed.is_empty() {
+            (
                 format!(
-                    "Deleted {}, failed {}: {}",
-                    deleted.len(),
+                    "Applied {}, failed {}: {}",
+                    succeeded.len(),
                     failed.len(),
-                    failed.first().map(|(_, e)| e.as_str()).unwrap_or("")
+                    failed[0]
                 ),
                 StatusLevel::Error,
+            )
+        } else if cancelled > 0 {
+            (
+                format!("Cancelled: {}, {} left staged", summary, cancelled),
+                StatusLevel::Warning,
+            )
+        } else if self.state.dry_run {
+            (
+                format!("[DRY RUN] Would have {}", summary),
+                StatusLevel::Success,
+            )
+        } else {
+            (format!("Successfully {}", summary), StatusLevel::Success)
+        };
+        self.state.set_status(msg, level);
+
+        if let Some(e) = self.audit_error.take() {
+            self.state.set_status(
+                format!("Could not write audit log: {:#}", e),
+                StatusLevel::Error,
             );
         }
+
+        // Leave the results up until they're dismissed.
+        self.state.jobs_done = true;
+        self.state.clear_staging_history();
+        self.state.cancelling = false;
+        self.state.staged_selected_index = 0;
+    }
+
+    fn record_audit(
+        &self,
+        repo_name: &str,
+        action: RepoAction,
+        error: Option<String>,
+    ) -> Result<()> {
+        let repo_id = self
+            .state
+            .repositories
+            .iter()
+            .find(|r| r.full_name == repo_name)
+            .map(|r| r.id);
+        self.audit.record(&AuditEntry::new(
+            self.state.login.as_deref(),
+            repo_name,
+            repo_id,
+            action,
+            self.state.dry_run,
+            error,
+        ))
+    }
+}
+
+/// Render e.g. "deleted 3 repos, archived 1 repo" for the completed actions.
+fn summarize_actions(done: &[(String, RepoAction)]) -> String {
+    let parts: Vec<String> = [
+        RepoAction::Delete,
+        RepoAction::Archive,
+        RepoAction::Unarchive,
+    ]
+    .into_iter()
+    .filter_map(|action| {
+        let count = done.iter().filter(|(_, a)| *a == action).count();
+        (count > 0).then(|| {
+            format!(
+                "{} {} repo{}",
+                action.past_tense(),
+                count,
+                if count == 1 { "" } else { "s" }
+            )
+        })
+    })
+    .collect();
+
+    if parts.is_empty() {
+        "deleted 0 repos".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Bring a listing fetched before `changes` were applied up to date.
+fn apply_changes(changes: &[(String, RepoAction)], repos: &mut Vec<Repository>) {
+    for (name, action) in changes {
+        match action {
+            RepoAction::Delete => repos.retain(|r| &r.full_name != name),
+            RepoAction::Archive | RepoAction::Unarchive => {
+                if let Some(repo) = repos.iter_mut().find(|r| &r.full_name == name) {
+                    repo.archived = *action == RepoAction::Archive;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::github::types::RepoDetails;
+    use crate::state::SortMode;
+
+    async fn app() -> App {
+        App {
+            state: AppState::new(true),
+            fuzzy: FuzzyMatcher::new(),
+            client: GitHubClient::new("token", &Host::default()).await.unwrap(),
+            backup: None,
+            audit: AuditLog::new().unwrap(),
+            audit_error: None,
+            plan_path: None,
+            pending_plan: None,
+            concurrency: 1,
+            cancel: Arc::new(AtomicBool::new(false)),
+            cache_path: None,
+            page_cache: PageCache::default(),
+            applied_during_refresh: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn streamed_pages_keep_the_selection() {
+        let mut app = app().await;
+        app.state.sort_mode = SortMode::Name;
+
+        app.add_page(vec![
+            Repository::fixture("octo/b"),
+            Repository::fixture("octo/d"),
+        ]);
+        app.state.select_repo("octo/d");
+        assert_eq!(app.state.selected_index, 1);
+
+        app.add_page(vec![
+            Repository::fixture("octo/a"),
+            Repository::fixture("octo/c"),
+        ]);
+        assert_eq!(app.state.selected_repo().unwrap().full_name, "octo/d");
+        assert_eq!(app.state.selected_index, 3);
+    }
+
+    #[tokio::test]
+    async fn refresh_drops_repos_deleted_while_it_ran() {
+        let mut app = app().await;
+        app.applied_during_refresh = vec![
+            ("octo/gone".to_string(), RepoAction::Delete),
+            ("octo/old".to_string(), RepoAction::Archive),
+        ];
+
+        app.finish_loading(Ok(RepoListing {
+            repos: vec![
+                Repository::fixture("octo/gone"),
+                Repository::fixture("octo/old"),
+                Repository::fixture("octo/kept"),
+            ],
+            org_errors: Vec::new(),
+            pages: PageCache::default(),
+        }));
+
+        let names: Vec<&str> = app
+            .state
+            .repositories
+            .iter()
+            .map(|r| r.full_name.as_str())
+            .collect();
+        assert_eq!(names, ["octo/old", "octo/kept"]);
+        assert!(app.state.repositories[0].archived);
+        assert!(app.applied_during_refresh.is_empty());
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_fetched_details() {
+        let mut app = app().await;
+        let details = RepoDetails {
+            parent: None,
+            subscribers: 3,
+            open_pulls: 1,
+        };
+        app.state.details = HashMap::from([
+            ("octo/a".to_string(), DetailsState::Loaded(details.clone())),
+            ("octo/b".to_string(), DetailsState::Failed("timeout".into())),
+            ("octo/gone".to_string(), DetailsState::Loaded(details)),
+        ]);
+
+        app.finish_loading(Ok(RepoListing {
+            repos: vec![Repository::fixture("octo/a"), Repository::fixture("octo/b")],
+            org_errors: Vec::new(),
+            pages: PageCache::default(),
+        }));
+
+        assert!(matches!(
+            app.state.details.get("octo/a"),
+            Some(DetailsState::Loaded(d)) if d.subscribers == 3
+        ));
+        // Failed fetches are retried; repos that are gone are dropped.
+        assert_eq!(app.state.details.len(), 1);
     }
 }
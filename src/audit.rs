@@ -0,0 +1,167 @@
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::state::RepoAction;
+
+/// One line of the audit log: a single attempt to act on a repo.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub user: Option<String>,
+    pub repo: String,
+    pub repo_id: Option<u64>,
+    pub action: RepoAction,
+    pub dry_run: bool,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(
+        user: Option<&str>,
+        repo: &str,
+        repo_id: Option<u64>,
+        action: RepoAction,
+        dry_run: bool,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            user: user.map(String::from),
+            repo: repo.to_string(),
+            repo_id,
+            action,
+            dry_run,
+            success: error.is_none(),
+            error,
+        }
+    }
+}
+
+/// Append-only JSONL record of everything ghune has deleted or archived.
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new() -> Result<Self> {
+        let path = dirs::data_dir()
+            .ok_or_else(|| eyre!("Could not find data directory"))?
+            .join("ghune")
+            .join("audit.jsonl");
+        Ok(Self { path })
+    }
+
+    pub fn record(&self, entry: &AuditEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .wrap_err_with(|| format!("Could not open audit log {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    /// All recorded entries, oldest first. Lines that fail to parse are
+    /// skipped so one corrupt write doesn't hide the rest of the history.
+    pub fn read_all(&self) -> Result<Vec<AuditEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let file = fs::File::open(&self.path)?;
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if let Ok(entry) = serde_json::from_str(&line?) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_in(dir: &tempfile::TempDir) -> AuditLog {
+        AuditLog {
+            path: dir.path().join("ghune").join("audit.jsonl"),
+        }
+    }
+
+    #[test]
+    fn appends_entries_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = log_in(&dir);
+        assert!(log.read_all().unwrap().is_empty());
+
+        log.record(&AuditEntry::new(
+            Some("octo"),
+            "octo/a",
+            Some(1),
+            RepoAction::Delete,
+            false,
+            None,
+        ))
+        .unwrap();
+        log.record(&AuditEntry::new(
+            Some("octo"),
+            "octo/b",
+            None,
+            RepoAction::Archive,
+            true,
+            Some("Not Found".into()),
+        ))
+        .unwrap();
+
+        let entries = log.read_all().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].repo, "octo/a");
+        assert!(entries[0].success);
+        assert_eq!(entries[1].repo, "octo/b");
+        assert_eq!(entries[1].action, RepoAction::Archive);
+        assert!(entries[1].dry_run);
+        assert!(!entries[1].success);
+        assert_eq!(entries[1].error.as_deref(), Some("Not Found"));
+    }
+
+    #[test]
+    fn skips_corrupt_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = log_in(&dir);
+        let entry = AuditEntry::new(None, "octo/a", None, RepoAction::Delete, false, None);
+        log.record(&entry).unwrap();
+        // A write cut short, then a line from some other tool.
+        let mut file = OpenOptions::new().append(true).open(log.path()).unwrap();
+        writeln!(file, "{{\"timestamp\":\"2024-01-01T00:00:00Z\",\"repo\":").unwrap();
+        writeln!(file, "not json").unwrap();
+        log.record(&AuditEntry::new(
+            None,
+            "octo/b",
+            None,
+            RepoAction::Unarchive,
+            false,
+            None,
+        ))
+        .unwrap();
+
+        let repos: Vec<String> = log
+            .read_all()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.repo)
+            .collect();
+        assert_eq!(repos, ["octo/a", "octo/b"]);
+    }
+}
@@ -1,51 +1,240 @@
 use color_eyre::eyre::{eyre, Result};
-use std::fs;
+use std::fmt;
 use std::io::{self, Write};
 use std::path::PathBuf;
 
-#[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
+use crate::credentials::{gh_cli_token, CredentialStore, FileStore, KeyringStore, TokenStore};
+use crate::github::Host;
 
-fn token_file_path() -> Result<PathBuf> {
-    let config_dir = dirs::config_dir()
-        .ok_or_else(|| eyre!("Could not find config directory"))?
-        .join("ghune");
-    Ok(config_dir.join("token"))
+/// Profile used when `--profile` isn't given. Its token lives where the
+/// single token did before profiles existed.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Environment variables checked for a github.com token, in order.
+const TOKEN_ENV_VARS: [&str; 2] = ["GHUNE_TOKEN", "GITHUB_TOKEN"];
+
+/// Environment variables checked for an enterprise token, in order. These
+/// follow the GitHub CLI, so a github.com `GITHUB_TOKEN` in CI isn't sent to
+/// another host.
+const ENTERPRISE_TOKEN_ENV_VARS: [&str; 3] = [
+    "GHUNE_TOKEN",
+    "GH_ENTERPRISE_TOKEN",
+    "GITHUB_ENTERPRISE_TOKEN",
+];
+
+/// Where the token in use came from, for `ghune auth status`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenSource {
+    Stdin,
+    Env(&'static str),
+    /// The profile's token in the named credential store.
+    Store(&'static str),
+    GhCli(PathBuf),
+    /// Typed in at the prompt.
+    Prompt,
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenSource::Stdin => write!(f, "standard input (--token-stdin)"),
+            TokenSource::Env(var) => write!(f, "{} environment variable", var),
+            TokenSource::Store(store) => write!(f, "stored token ({})", store),
+            TokenSource::GhCli(path) => write!(f, "GitHub CLI ({})", path.display()),
+            TokenSource::Prompt => write!(f, "prompt"),
+        }
+    }
+}
+
+fn read_stdin_token() -> Result<String> {
+    let mut token = String::new();
+    io::stdin().read_line(&mut token)?;
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(eyre!("No token on standard input"));
+    }
+    Ok(token.to_string())
 }
 
-pub struct TokenManager;
+/// Stores one GitHub token per host and named profile.
+pub struct TokenManager {
+    host: Host,
+    profile: String,
+    store: Box<dyn CredentialStore>,
+    /// Where tokens were kept before `store`; anything found here is moved
+    /// into `store` the first time it's read.
+    legacy: Option<Box<dyn CredentialStore>>,
+}
 
 impl TokenManager {
-    pub fn new() -> Result<Self> {
-        Ok(Self)
+    pub fn new(host: &Host, profile: Option<&str>, store: TokenStore) -> Result<Self> {
+        let profile = profile.unwrap_or(DEFAULT_PROFILE);
+        let valid = !profile.is_empty()
+            && profile
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(eyre!(
+                "Invalid profile name '{}': use letters, digits, '-' and '_'",
+                profile
+            ));
+        }
+
+        let keyring = match store {
+            TokenStore::File => None,
+            TokenStore::Auto => KeyringStore::open(host)?,
+            TokenStore::Keyring => Some(KeyringStore::open(host)?.ok_or_else(|| {
+                eyre!("No OS keyring is reachable; use --token-store file instead")
+            })?),
+        };
+        let file = Box::new(FileStore::new(host)?);
+        Ok(match keyring {
+            Some(keyring) => Self::with_stores(host, profile, Box::new(keyring), Some(file)),
+            None => Self::with_stores(host, profile, file, None),
+        })
+    }
+
+    fn with_stores(
+        host: &Host,
+        profile: &str,
+        store: Box<dyn CredentialStore>,
+        legacy: Option<Box<dyn CredentialStore>>,
+    ) -> Self {
+        Self {
+            host: host.clone(),
+            profile: profile.to_string(),
+            store,
+            legacy,
+        }
+    }
+
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+
+    /// Name of the store new tokens are written to.
+    pub fn store_name(&self) -> &'static str {
+        self.store.name()
+    }
+
+    /// Every profile with a stored token, default first.
+    pub fn list_profiles(&self) -> Result<Vec<String>> {
+        let mut named = self.store.profiles()?;
+        if let Some(legacy) = &self.legacy {
+            named.extend(legacy.profiles()?);
+        }
+        named.sort();
+        named.dedup();
+
+        let mut profiles = Vec::new();
+        if let Some(i) = named.iter().position(|p| p == DEFAULT_PROFILE) {
+            profiles.push(named.remove(i));
+        }
+        profiles.extend(named);
+        Ok(profiles)
     }
 
     pub fn get_token(&self) -> Result<Option<String>> {
-        let path = token_file_path()?;
-        if path.exists() {
-            let token = fs::read_to_string(&path)?.trim().to_string();
-            if !token.is_empty() {
-                return Ok(Some(token));
+        if let Some(token) = self.store.get(&self.profile)? {
+            return Ok(Some(token));
+        }
+
+        let Some(legacy) = &self.legacy else {
+            return Ok(None);
+        };
+        let Some(token) = legacy.get(&self.profile)? else {
+            return Ok(None);
+        };
+        self.store.set(&self.profile, &token)?;
+        legacy.delete(&self.profile)?;
+        eprintln!(
+            "Moved the token for profile '{}' from the {} store into the {}.",
+            self.profile,
+            legacy.name(),
+            self.store.name()
+        );
+        Ok(Some(token))
+    }
+
+    /// Find a token without prompting or storing anything. Sources are
+    /// tried in order: `--token-stdin`, `GHUNE_TOKEN`, `GITHUB_TOKEN` (or
+    /// the enterprise variables for another host), the profile's stored
+    /// token, then the GitHub CLI's `hosts.yml`.
+    pub fn find_token(&self, stdin: bool) -> Result<Option<(String, TokenSource)>> {
+        let stdin = stdin.then(read_stdin_token).transpose()?;
+        self.find_token_in(stdin, |var| std::env::var(var).ok(), gh_cli_token)
+    }
+
+    /// `find_token` with the token read from standard input (if any), the
+    /// environment and the GitHub CLI lookup passed in.
+    fn find_token_in(
+        &self,
+        stdin: Option<String>,
+        env: impl Fn(&str) -> Option<String>,
+        gh_cli_token: impl FnOnce(&Host) -> Option<(String, PathBuf)>,
+    ) -> Result<Option<(String, TokenSource)>> {
+        if let Some(token) = stdin {
+            return Ok(Some((token, TokenSource::Stdin)));
+        }
+        let vars: &[&'static str] = if self.host.is_github_com() {
+            &TOKEN_ENV_VARS
+        } else {
+            &ENTERPRISE_TOKEN_ENV_VARS
+        };
+        for &var in vars {
+            if let Some(token) = env(var) {
+                let token = token.trim();
+                if !token.is_empty() {
+                    return Ok(Some((token.to_string(), TokenSource::Env(var))));
+                }
             }
         }
+        if let Some(token) = self.get_token()? {
+            return Ok(Some((token, TokenSource::Store(self.store.name()))));
+        }
+        if let Some((token, path)) = gh_cli_token(&self.host) {
+            return Ok(Some((token, TokenSource::GhCli(path))));
+        }
         Ok(None)
     }
 
-    pub fn get_or_prompt_token(&self) -> Result<String> {
-        if let Some(token) = self.get_token()? {
-            return Ok(token);
+    /// Like `find_token`, but prompt for a token if none of the sources has
+    /// one. A prompted token isn't stored until the caller has checked that
+    /// GitHub accepts it.
+    pub fn get_or_prompt_token(&self, stdin: bool) -> Result<(String, TokenSource)> {
+        if let Some(found) = self.find_token(stdin)? {
+            return Ok(found);
         }
 
-        let token = self.prompt_for_token()?;
-        self.store_token(&token)?;
-        Ok(token)
+        if self.profile == DEFAULT_PROFILE {
+            eprintln!("GitHub personal access token not found.");
+        } else {
+            eprintln!(
+                "GitHub personal access token for profile '{}' not found.",
+                self.profile
+            );
+        }
+        eprintln!();
+        Ok((self.prompt_for_token()?, TokenSource::Prompt))
+    }
+
+    /// Prompt for a new token, or read it from stdin, without storing it.
+    pub fn read_new_token(&self, stdin: bool) -> Result<String> {
+        if stdin {
+            read_stdin_token()
+        } else {
+            self.prompt_for_token()
+        }
     }
 
     fn prompt_for_token(&self) -> Result<String> {
-        eprintln!("GitHub personal access token not found.");
-        eprintln!();
-        eprintln!("Create one at: https://github.com/settings/tokens/new");
+        eprintln!("Create one at: {}/settings/tokens/new", self.host.web_url());
         eprintln!("Required scopes: 'delete_repo' and 'repo' (for private repos)");
+        eprintln!("Optional scope: 'read:org' (to manage organization repos)");
         eprintln!();
         eprint!("Enter token: ");
         io::stderr().flush()?;
@@ -60,27 +249,227 @@ impl TokenManager {
     }
 
     pub fn store_token(&self, token: &str) -> Result<()> {
-        let path = token_file_path()?;
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+        self.store.set(&self.profile, token)
+    }
+
+    pub fn clear_token(&self) -> Result<()> {
+        self.store.delete(&self.profile)?;
+        if let Some(legacy) = &self.legacy {
+            legacy.delete(&self.profile)?;
         }
-        fs::write(&path, format!("{}\n", token))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
 
-        #[cfg(unix)]
-        {
-            let mut perms = fs::metadata(&path)?.permissions();
-            perms.set_mode(0o600);
-            fs::set_permissions(&path, perms)?;
+    /// In-memory store; clones share the same tokens so tests can inspect
+    /// what the manager wrote.
+    #[derive(Clone, Default)]
+    struct MockStore {
+        tokens: Rc<RefCell<HashMap<String, String>>>,
+    }
+
+    impl MockStore {
+        fn with(profile: &str, token: &str) -> Self {
+            let store = Self::default();
+            store.set(profile, token).unwrap();
+            store
         }
 
-        Ok(())
+        fn token(&self, profile: &str) -> Option<String> {
+            self.tokens.borrow().get(profile).cloned()
+        }
     }
 
-    pub fn clear_token(&self) -> Result<()> {
-        let path = token_file_path()?;
-        if path.exists() {
-            fs::remove_file(&path)?;
+    impl CredentialStore for MockStore {
+        fn name(&self) -> &'static str {
+            "mock"
         }
-        Ok(())
+
+        fn get(&self, profile: &str) -> Result<Option<String>> {
+            Ok(self.token(profile))
+        }
+
+        fn set(&self, profile: &str, token: &str) -> Result<()> {
+            self.tokens
+                .borrow_mut()
+                .insert(profile.to_string(), token.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, profile: &str) -> Result<()> {
+            self.tokens.borrow_mut().remove(profile);
+            Ok(())
+        }
+
+        fn profiles(&self) -> Result<Vec<String>> {
+            Ok(self.tokens.borrow().keys().cloned().collect())
+        }
+    }
+
+    fn manager(profile: &str, store: &MockStore, legacy: Option<&MockStore>) -> TokenManager {
+        TokenManager::with_stores(
+            &Host::default(),
+            profile,
+            Box::new(store.clone()),
+            legacy.map(|l| Box::new(l.clone()) as Box<dyn CredentialStore>),
+        )
+    }
+
+    #[test]
+    fn stores_and_clears_per_profile() {
+        let store = MockStore::default();
+        let work = manager("work", &store, None);
+        work.store_token("work-token").unwrap();
+        manager(DEFAULT_PROFILE, &store, None)
+            .store_token("default-token")
+            .unwrap();
+
+        assert_eq!(work.get_token().unwrap().as_deref(), Some("work-token"));
+        work.clear_token().unwrap();
+        assert_eq!(work.get_token().unwrap(), None);
+        assert_eq!(
+            store.token(DEFAULT_PROFILE).as_deref(),
+            Some("default-token")
+        );
+    }
+
+    #[test]
+    fn migrates_legacy_token_on_first_read() {
+        let store = MockStore::default();
+        let legacy = MockStore::with("work", "plaintext");
+        let tokens = manager("work", &store, Some(&legacy));
+
+        assert_eq!(tokens.get_token().unwrap().as_deref(), Some("plaintext"));
+        assert_eq!(store.token("work").as_deref(), Some("plaintext"));
+        assert_eq!(legacy.token("work"), None);
+    }
+
+    #[test]
+    fn prefers_store_over_legacy() {
+        let store = MockStore::with("work", "keyring");
+        let legacy = MockStore::with("work", "plaintext");
+        let tokens = manager("work", &store, Some(&legacy));
+
+        assert_eq!(tokens.get_token().unwrap().as_deref(), Some("keyring"));
+        // Left alone until the user clears it.
+        assert_eq!(legacy.token("work").as_deref(), Some("plaintext"));
+        tokens.clear_token().unwrap();
+        assert_eq!(legacy.token("work"), None);
+    }
+
+    #[test]
+    fn lists_profiles_from_both_stores_default_first() {
+        let store = MockStore::with("work", "a");
+        let legacy = MockStore::with(DEFAULT_PROFILE, "b");
+        legacy.set("alt", "c").unwrap();
+        legacy.set("work", "d").unwrap();
+        let tokens = manager("work", &store, Some(&legacy));
+
+        assert_eq!(
+            tokens.list_profiles().unwrap(),
+            vec![DEFAULT_PROFILE, "alt", "work"]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_profile_names() {
+        assert!(TokenManager::new(&Host::default(), Some("../evil"), TokenStore::File).is_err());
+        assert!(TokenManager::new(&Host::default(), Some(""), TokenStore::File).is_err());
+    }
+
+    /// Every source but the prompt holds a token; `missing` are left out.
+    fn find(tokens: &TokenManager, missing: &[&str]) -> Option<(String, TokenSource)> {
+        let has = |source: &str| !missing.contains(&source);
+        tokens
+            .find_token_in(
+                has("stdin").then(|| "from-stdin".to_string()),
+                |var| match var {
+                    "GHUNE_TOKEN" if has("GHUNE_TOKEN") => Some("from-ghune-env".into()),
+                    "GITHUB_TOKEN" if has("GITHUB_TOKEN") => Some("from-github-env".into()),
+                    _ => None,
+                },
+                |_| has("gh").then(|| ("from-gh".into(), PathBuf::from("hosts.yml"))),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_tokens_in_precedence_order() {
+        let stored = MockStore::with(DEFAULT_PROFILE, "from-store");
+        let empty = MockStore::default();
+        let with_store = manager(DEFAULT_PROFILE, &stored, None);
+        let without_store = manager(DEFAULT_PROFILE, &empty, None);
+
+        assert_eq!(
+            find(&with_store, &[]),
+            Some(("from-stdin".into(), TokenSource::Stdin))
+        );
+        assert_eq!(
+            find(&with_store, &["stdin"]),
+            Some(("from-ghune-env".into(), TokenSource::Env("GHUNE_TOKEN")))
+        );
+        assert_eq!(
+            find(&with_store, &["stdin", "GHUNE_TOKEN"]),
+            Some(("from-github-env".into(), TokenSource::Env("GITHUB_TOKEN")))
+        );
+        assert_eq!(
+            find(&with_store, &["stdin", "GHUNE_TOKEN", "GITHUB_TOKEN"]),
+            Some(("from-store".into(), TokenSource::Store("mock")))
+        );
+        assert_eq!(
+            find(&without_store, &["stdin", "GHUNE_TOKEN", "GITHUB_TOKEN"]),
+            Some((
+                "from-gh".into(),
+                TokenSource::GhCli(PathBuf::from("hosts.yml"))
+            ))
+        );
+        assert_eq!(
+            find(
+                &without_store,
+                &["stdin", "GHUNE_TOKEN", "GITHUB_TOKEN", "gh"]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn skips_blank_env_tokens() {
+        let store = MockStore::with(DEFAULT_PROFILE, "from-store");
+        let tokens = manager(DEFAULT_PROFILE, &store, None);
+        let found = tokens
+            .find_token_in(None, |_| Some("  ".into()), |_| None)
+            .unwrap();
+        assert_eq!(
+            found,
+            Some(("from-store".into(), TokenSource::Store("mock")))
+        );
+    }
+
+    #[test]
+    fn enterprise_hosts_ignore_github_token() {
+        let store = MockStore::default();
+        let tokens = TokenManager::with_stores(
+            &Host::parse("ghe.example.com").unwrap(),
+            DEFAULT_PROFILE,
+            Box::new(store),
+            None,
+        );
+        let env = |var: &str| {
+            (var == "GITHUB_TOKEN" || var == "GH_ENTERPRISE_TOKEN").then(|| var.to_string())
+        };
+        assert_eq!(
+            tokens.find_token_in(None, env, |_| None).unwrap(),
+            Some((
+                "GH_ENTERPRISE_TOKEN".into(),
+                TokenSource::Env("GH_ENTERPRISE_TOKEN")
+            ))
+        );
     }
 }
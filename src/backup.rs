@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::eyre::{eyre, Result};
+use tokio::process::Command;
+
+use crate::github::{types::Repository, GitHubClient};
+
+#[derive(Debug, Clone)]
+pub struct BackupOptions {
+    pub dir: PathBuf,
+    pub include_metadata: bool,
+}
+
+/// Saves a verified git bundle (and optionally issue/PR metadata) of a repo
+/// before it is deleted.
+#[derive(Clone)]
+pub struct Backup {
+    options: BackupOptions,
+    auth_header: String,
+}
+
+impl Backup {
+    pub fn new(token: &str, options: BackupOptions) -> Self {
+        let credentials = STANDARD.encode(format!("x-access-token:{}", token));
+        Self {
+            options,
+            auth_header: format!("Authorization: Basic {}", credentials),
+        }
+    }
+
+    /// Mirror-clone `repo`, bundle every ref and verify the bundle. Returns
+    /// the path of the bundle, or of the (empty) mirror if the repo has no
+    /// refs to bundle.
+    pub async fn run(&self, client: &GitHubClient, repo: &Repository) -> Result<PathBuf> {
+        let owner_dir = self.options.dir.join(&repo.owner);
+        tokio::fs::create_dir_all(&owner_dir).await?;
+
+        let mirror = owner_dir.join(format!("{}.git", repo.name));
+        let bundle = owner_dir.join(format!("{}.bundle", repo.name));
+        if tokio::fs::try_exists(&mirror).await? {
+            tokio::fs::remove_dir_all(&mirror).await?;
+        }
+
+        let clone_url = format!("{}.git", repo.html_url);
+        self.git(None, &["clone", "--mirror", "--quiet", &clone_url])
+            .arg(&mirror)
+            .run()
+            .await?;
+
+        let refs = self.git(Some(&mirror), &["for-each-ref"]).output().await?;
+        if refs.trim().is_empty() {
+            // Nothing to bundle: git refuses to create an empty bundle, and an
+            // empty repo has no history to lose.
+            self.write_metadata(client, repo, &owner_dir).await?;
+            return Ok(mirror);
+        }
+
+        self.git(Some(&mirror), &["bundle", "create", "--quiet"])
+            .arg(&bundle)
+            .arg("--all")
+            .run()
+            .await?;
+        self.git(Some(&mirror), &["bundle", "verify", "--quiet"])
+            .arg(&bundle)
+            .run()
+            .await?;
+        tokio::fs::remove_dir_all(&mirror).await?;
+
+        self.write_metadata(client, repo, &owner_dir).await?;
+        Ok(bundle)
+    }
+
+    async fn write_metadata(
+        &self,
+        client: &GitHubClient,
+        repo: &Repository,
+        owner_dir: &Path,
+    ) -> Result<()> {
+        if !self.options.include_metadata {
+            return Ok(());
+        }
+
+        let issues = client.list_issues_json(&repo.full_name).await?;
+        let pulls = client.list_pulls_json(&repo.full_name).await?;
+        for (suffix, items) in [("issues", issues), ("pulls", pulls)] {
+            let path = owner_dir.join(format!("{}.{}.json", repo.name, suffix));
+            tokio::fs::write(&path, serde_json::to_vec_pretty(&items)?).await?;
+        }
+        Ok(())
+    }
+
+    fn git(&self, dir: Option<&Path>, args: &[&str]) -> GitCommand {
+        let mut cmd = Command::new("git");
+        if let Some(dir) = dir {
+            cmd.arg("-C").arg(dir);
+        }
+        cmd.args(args)
+            // Pass credentials through the environment so the token never
+            // appears in the process list or the mirror's config.
+            .env("GIT_CONFIG_COUNT", "1")
+            .env("GIT_CONFIG_KEY_0", "http.extraHeader")
+            .env("GIT_CONFIG_VALUE_0", &self.auth_header)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::null());
+        GitCommand(cmd)
+    }
+}
+
+struct GitCommand(Command);
+
+impl GitCommand {
+    fn arg(mut self, arg: impl AsRef<std::ffi::OsStr>) -> Self {
+        self.0.arg(arg);
+        self
+    }
+
+    async fn run(self) -> Result<()> {
+        self.output().await.map(|_| ())
+    }
+
+    async fn output(mut self) -> Result<String> {
+        let output = self.0.output().await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(eyre!("git failed: {}", stderr.trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
@@ -0,0 +1,247 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::Deserialize;
+
+use crate::{
+    filter::{self, RepoFilter},
+    github::Host,
+    keymap::{Keymap, KeysConfig},
+    query::Query,
+    safety::SafetyConfig,
+    state::SortMode,
+    tui::theme::Theme,
+};
+
+/// Settings read from `config.toml` in the ghune config directory. Every
+/// section is optional; a missing file means all defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub defaults: Defaults,
+    pub safety: SafetyConfig,
+    pub keys: KeysConfig,
+    pub theme: Theme,
+    pub login: LoginConfig,
+    pub github: GitHubConfig,
+}
+
+/// The `[github]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitHubConfig {
+    /// GitHub Enterprise Server host (or URL) to use instead of github.com.
+    pub host: Option<String>,
+}
+
+/// The `[login]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoginConfig {
+    /// Client ID of the OAuth app `ghune login` authorizes through the
+    /// device flow. Without one, `ghune login` asks for a personal access
+    /// token instead.
+    pub client_id: Option<String>,
+}
+
+/// The `[defaults]` section: what the TUI shows when it starts and how it
+/// applies changes. Command line flags take precedence.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Defaults {
+    /// Start in dry-run mode unless `--no-dry-run` is passed.
+    pub dry_run: bool,
+    pub concurrency: Option<usize>,
+    pub query: String,
+    pub sort: SortMode,
+    pub owner: Option<String>,
+    pub forks_only: bool,
+    pub private_only: bool,
+    pub public_only: bool,
+    pub archived_only: bool,
+    pub empty_only: bool,
+    pub stale: Option<String>,
+}
+
+impl Defaults {
+    pub fn filter(&self) -> Result<RepoFilter> {
+        if let Some(err) = Query::parse(&self.query).errors.first() {
+            return Err(eyre!("defaults.query: {}", err));
+        }
+        if self.private_only && self.public_only {
+            return Err(eyre!(
+                "defaults: private_only and public_only can't both be set"
+            ));
+        }
+        let stale = self
+            .stale
+            .as_deref()
+            .map(filter::parse_stale)
+            .transpose()
+            .map_err(|e| eyre!("defaults.stale: {}", e))?;
+
+        Ok(RepoFilter {
+            query: self.query.clone(),
+            private_only: self.private_only,
+            public_only: self.public_only,
+            forks_only: self.forks_only,
+            archived_only: self.archived_only,
+            empty_only: self.empty_only,
+            stale,
+            owner: self.owner.clone(),
+            sort: self.sort,
+        })
+    }
+}
+
+impl Config {
+    pub fn path() -> Result<PathBuf> {
+        Ok(dirs::config_dir()
+            .ok_or_else(|| eyre!("Could not find config directory"))?
+            .join("ghune")
+            .join("config.toml"))
+    }
+
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::path()?)
+    }
+
+    fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        let config: Self = toml::from_str(&contents)
+            .wrap_err_with(|| format!("Invalid config {}", path.display()))?;
+        config
+            .validate()
+            .wrap_err_with(|| format!("Invalid config {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Catch mistakes serde can't: bad values inside strings, conflicting
+    /// options and clashing key bindings.
+    fn validate(&self) -> Result<()> {
+        self.defaults.filter()?;
+        if self.defaults.concurrency == Some(0) {
+            return Err(eyre!("defaults.concurrency must be at least 1"));
+        }
+        if self.safety.protected.iter().any(|p| p.trim().is_empty()) {
+            return Err(eyre!("safety.protected contains an empty pattern"));
+        }
+        Keymap::new(&self.keys).map_err(|e| eyre!(e))?;
+        if let Some(host) = &self.github.host {
+            Host::parse(host).map_err(|e| eyre!("github.host: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    use crate::keymap::KeyAction;
+    use crate::state::AppMode;
+
+    /// Load `contents` as a config file, with the error chain on one line.
+    fn load(contents: &str) -> std::result::Result<Config, String> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, contents).unwrap();
+        Config::load_from(&path)
+            .map_err(|e| format!("{:#}", e).replace(&path.display().to_string(), "config.toml"))
+    }
+
+    #[test]
+    fn missing_file_is_all_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::load_from(&dir.path().join("config.toml")).unwrap();
+        assert!(!config.defaults.dry_run);
+        assert_eq!(config.safety.star_threshold, 25);
+    }
+
+    #[test]
+    fn loads_every_section() {
+        let config = load(
+            r##"
+            [defaults]
+            dry_run = true
+            query = "is:fork stars:<5"
+            stale = "90d"
+
+            [safety]
+            protected = ["octo/*"]
+
+            [theme]
+            accent = "#ff8800"
+            muted = "dark gray"
+            selection = "236"
+
+            [keys.search]
+            refresh = ["F5"]
+            "##,
+        )
+        .unwrap();
+        assert!(config.defaults.dry_run);
+        assert!(config.defaults.filter().unwrap().stale.is_some());
+        assert!(config.safety.is_protected("octo/site"));
+        assert_eq!(config.theme.accent, Color::Rgb(0xff, 0x88, 0x00));
+        assert_eq!(config.theme.muted, Color::DarkGray);
+        assert_eq!(config.theme.selection, Color::Indexed(236));
+        assert_eq!(config.theme.danger, Theme::default().danger);
+        let keymap = Keymap::new(&config.keys).unwrap();
+        assert_eq!(
+            keymap.hint(AppMode::Search, KeyAction::Refresh).as_deref(),
+            Some("F5")
+        );
+    }
+
+    #[test]
+    fn reports_invalid_config() {
+        for (contents, expected) in [
+            (
+                "[defaults]\nconcurrency = 0\n",
+                "defaults.concurrency must be at least 1",
+            ),
+            ("[defaults]\nstale = \"soon\"\n", "defaults.stale:"),
+            (
+                "[safety]\nprotected = [\" \"]\n",
+                "safety.protected contains an empty pattern",
+            ),
+            ("[defualts]\n", "unknown field `defualts`"),
+        ] {
+            let err = load(contents).unwrap_err();
+            assert!(err.starts_with("Invalid config config.toml: "), "{}", err);
+            assert!(err.contains(expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn reports_invalid_theme() {
+        let err = load("[theme]\naccent = \"chartreuse\"\n").unwrap_err();
+        assert!(err.contains("unknown colour 'chartreuse'"), "{}", err);
+        let err = load("[theme]\nbackground = \"black\"\n").unwrap_err();
+        assert!(err.contains("unknown field `background`"), "{}", err);
+    }
+
+    #[test]
+    fn reports_invalid_keys() {
+        let err = load("[keys.search]\nrefresh = [\"ctrl-p\"]\n").unwrap_err();
+        assert_eq!(
+            err,
+            "Invalid config config.toml: \
+             [keys.search]: C-p is bound to both 'refresh' and 'toggle_private'"
+        );
+        let err = load("[keys.history]\nup = [\"hyper-k\"]\n").unwrap_err();
+        assert!(
+            err.ends_with("[keys.history] up: unknown modifier 'hyper' in 'hyper-k'"),
+            "{}",
+            err
+        );
+        let err = load("[keys.search]\nfly = [\"f\"]\n").unwrap_err();
+        assert!(err.contains("unknown variant `fly`"), "{}", err);
+    }
+}
@@ -0,0 +1,312 @@
+use color_eyre::eyre::{eyre, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+use crate::{auth::DEFAULT_PROFILE, github::Host};
+
+/// Service name tokens are filed under in the OS keyring.
+const KEYRING_SERVICE: &str = "ghune";
+
+pub(crate) fn config_dir() -> Result<PathBuf> {
+    Ok(dirs::config_dir()
+        .ok_or_else(|| eyre!("Could not find config directory"))?
+        .join("ghune"))
+}
+
+/// Where a host's tokens are kept: the config directory itself for
+/// github.com, `hosts/<host>` inside it for anything else.
+fn host_dir(host: &Host) -> Result<PathBuf> {
+    let dir = config_dir()?;
+    if host.is_github_com() {
+        return Ok(dir);
+    }
+    Ok(dir.join("hosts").join(host.name().replace(':', "_")))
+}
+
+/// Where tokens are kept, chosen with `--token-store`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum TokenStore {
+    /// The OS keyring if one is reachable, otherwise a file.
+    #[default]
+    Auto,
+    /// The OS keyring (Secret Service, macOS Keychain or Windows Credential
+    /// Manager); fails if it isn't reachable.
+    Keyring,
+    /// A plaintext file only readable by the current user.
+    File,
+}
+
+/// Somewhere a token can be kept per profile.
+pub trait CredentialStore {
+    /// Short name shown to users, e.g. "keyring".
+    fn name(&self) -> &'static str;
+    fn get(&self, profile: &str) -> Result<Option<String>>;
+    fn set(&self, profile: &str, token: &str) -> Result<()>;
+    fn delete(&self, profile: &str) -> Result<()>;
+    /// Profiles with a stored token, in no particular order.
+    fn profiles(&self) -> Result<Vec<String>>;
+}
+
+/// Tokens in `~/.config/ghune/token` (default profile) and
+/// `~/.config/ghune/profiles/<name>/token`, readable only by the owner.
+/// Enterprise hosts get the same layout under `~/.config/ghune/hosts/<host>`.
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(host: &Host) -> Result<Self> {
+        Ok(Self {
+            dir: host_dir(host)?,
+        })
+    }
+
+    fn path(&self, profile: &str) -> PathBuf {
+        if profile == DEFAULT_PROFILE {
+            return self.dir.join("token");
+        }
+        self.dir.join("profiles").join(profile).join("token")
+    }
+}
+
+impl CredentialStore for FileStore {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn get(&self, profile: &str) -> Result<Option<String>> {
+        let path = self.path(profile);
+        if path.exists() {
+            let token = fs::read_to_string(&path)?.trim().to_string();
+            if !token.is_empty() {
+                return Ok(Some(token));
+            }
+        }
+        Ok(None)
+    }
+
+    fn set(&self, profile: &str, token: &str) -> Result<()> {
+        let path = self.path(profile);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, format!("{}\n", token))?;
+
+        #[cfg(unix)]
+        {
+            let mut perms = fs::metadata(&path)?.permissions();
+            perms.set_mode(0o600);
+            fs::set_permissions(&path, perms)?;
+        }
+
+        Ok(())
+    }
+
+    fn delete(&self, profile: &str) -> Result<()> {
+        let path = self.path(profile);
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        Ok(())
+    }
+
+    fn profiles(&self) -> Result<Vec<String>> {
+        let mut profiles = Vec::new();
+        if self.path(DEFAULT_PROFILE).exists() {
+            profiles.push(DEFAULT_PROFILE.to_string());
+        }
+
+        let dir = self.dir.join("profiles");
+        if dir.exists() {
+            for entry in fs::read_dir(&dir)? {
+                let entry = entry?;
+                if entry.path().join("token").exists() {
+                    profiles.push(entry.file_name().to_string_lossy().into_owned());
+                }
+            }
+        }
+        Ok(profiles)
+    }
+}
+
+/// Tokens in the OS keyring, under the service `ghune` for github.com and
+/// `ghune:<host>` for enterprise hosts. Keyrings can't be enumerated, so the
+/// names of profiles stored here (not their tokens) are tracked in a
+/// `keyring-profiles` file next to where the file store would keep them.
+pub struct KeyringStore {
+    service: String,
+    index: PathBuf,
+}
+
+impl KeyringStore {
+    /// Connect to the keyring, or `None` if there isn't one reachable (e.g.
+    /// no desktop session on a headless box).
+    pub fn open(host: &Host) -> Result<Option<Self>> {
+        let service = if host.is_github_com() {
+            KEYRING_SERVICE.to_string()
+        } else {
+            format!("{}:{}", KEYRING_SERVICE, host.name())
+        };
+        let probe = keyring::Entry::new(&service, "__probe__")?;
+        match probe.get_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(Some(Self {
+                service,
+                index: host_dir(host)?.join("keyring-profiles"),
+            })),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn entry(&self, profile: &str) -> Result<keyring::Entry> {
+        Ok(keyring::Entry::new(&self.service, profile)?)
+    }
+
+    fn write_index(&self, profiles: &[String]) -> Result<()> {
+        if let Some(parent) = self.index.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut contents = profiles.join("\n");
+        contents.push('\n');
+        fs::write(&self.index, contents)?;
+        Ok(())
+    }
+}
+
+impl CredentialStore for KeyringStore {
+    fn name(&self) -> &'static str {
+        "keyring"
+    }
+
+    fn get(&self, profile: &str) -> Result<Option<String>> {
+        match self.entry(profile)?.get_password() {
+            Ok(token) => Ok(Some(token)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set(&self, profile: &str, token: &str) -> Result<()> {
+        self.entry(profile)?.set_password(token)?;
+        let mut profiles = self.profiles()?;
+        if !profiles.iter().any(|p| p == profile) {
+            profiles.push(profile.to_string());
+            self.write_index(&profiles)?;
+        }
+        Ok(())
+    }
+
+    fn delete(&self, profile: &str) -> Result<()> {
+        match self.entry(profile)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(e.into()),
+        }
+        let mut profiles = self.profiles()?;
+        profiles.retain(|p| p != profile);
+        self.write_index(&profiles)
+    }
+
+    fn profiles(&self) -> Result<Vec<String>> {
+        if !self.index.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(fs::read_to_string(&self.index)?
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct GhHost {
+    oauth_token: Option<String>,
+}
+
+/// Directory the GitHub CLI keeps its config in, following its own lookup.
+fn gh_config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("GH_CONFIG_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if cfg!(windows) {
+        return Some(dirs::config_dir()?.join("GitHub CLI"));
+    }
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("gh"));
+    }
+    Some(dirs::home_dir()?.join(".config").join("gh"))
+}
+
+/// The token `gh auth login` saved for `host`, and the file it came from.
+/// Recent `gh` versions keep tokens in the OS keyring instead, in which case
+/// there is nothing to find here.
+pub fn gh_cli_token(host: &Host) -> Option<(String, PathBuf)> {
+    let path = gh_config_dir()?.join("hosts.yml");
+    let token = gh_hosts_token(&path, host)?;
+    Some((token, path))
+}
+
+/// The token for `host` in the GitHub CLI `hosts.yml` at `path`. A file
+/// that can't be read or parsed is ignored with a warning rather than
+/// standing in the way of the other token sources.
+fn gh_hosts_token(path: &Path, host: &Host) -> Option<String> {
+    if !path.exists() {
+        return None;
+    }
+    let hosts = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| {
+            serde_yaml::from_str::<HashMap<String, GhHost>>(&contents).map_err(|e| e.to_string())
+        });
+    match hosts {
+        Ok(mut hosts) => hosts
+            .remove(host.name())
+            .and_then(|h| h.oauth_token)
+            .filter(|t| !t.is_empty()),
+        Err(e) => {
+            eprintln!("Ignoring {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosts_file(contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, contents.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn reads_gh_token_for_host() {
+        let file = hosts_file(
+            "github.com:\n    oauth_token: gho_abc\n    user: octo\n\
+             ghe.example.com:\n    user: octo\n",
+        );
+        let github = Host::default();
+        let enterprise = Host::parse("ghe.example.com").unwrap();
+        assert_eq!(
+            gh_hosts_token(file.path(), &github).as_deref(),
+            Some("gho_abc")
+        );
+        // Tokens kept in the keyring leave no oauth_token behind.
+        assert_eq!(gh_hosts_token(file.path(), &enterprise), None);
+    }
+
+    #[test]
+    fn ignores_missing_or_malformed_gh_hosts() {
+        let file = hosts_file("github.com: [oauth_token: gho_abc\n");
+        assert_eq!(gh_hosts_token(file.path(), &Host::default()), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("hosts.yml");
+        assert_eq!(gh_hosts_token(&missing, &Host::default()), None);
+    }
+}
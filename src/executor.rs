@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use color_eyre::eyre::{Result, WrapErr};
+use futures::StreamExt;
+
+use crate::{
+    backup::Backup,
+    github::{types::Repository, GitHubClient},
+    state::RepoAction,
+};
+
+/// How long a simulated action takes in dry-run mode.
+const DRY_RUN_DELAY: Duration = Duration::from_millis(400);
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub repo: Repository,
+    pub action: RepoAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    BackingUp,
+    Running,
+    Succeeded,
+    Failed(String),
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Succeeded | JobStatus::Failed(_) | JobStatus::Cancelled
+        )
+    }
+}
+
+/// Carry out a single staged action against GitHub.
+pub async fn apply(client: &GitHubClient, full_name: &str, action: RepoAction) -> Result<()> {
+    match action {
+        RepoAction::Delete => client.delete_repo(full_name).await,
+        RepoAction::Archive => client.set_archived(full_name, true).await,
+        RepoAction::Unarchive => client.set_archived(full_name, false).await,
+    }
+}
+
+/// Run `jobs` with at most `concurrency` in flight, calling `report` with the
+/// job's index on every status change. Once `cancel` is set, jobs that haven't
+/// started yet are reported as cancelled; in-flight ones run to completion.
+pub async fn run(
+    client: &GitHubClient,
+    backup: Option<&Backup>,
+    jobs: &[Job],
+    concurrency: usize,
+    dry_run: bool,
+    cancel: &AtomicBool,
+    report: impl Fn(usize, JobStatus),
+) {
+    let report = &report;
+    futures::stream::iter(jobs.iter().enumerate())
+        .for_each_concurrent(concurrency.max(1), |(index, job)| async move {
+            if cancel.load(Ordering::Relaxed) {
+                report(index, JobStatus::Cancelled);
+                return;
+            }
+
+            let result = if dry_run {
+                report(index, JobStatus::Running);
+                tokio::time::sleep(DRY_RUN_DELAY).await;
+                Ok(())
+            } else {
+                run_job(client, backup, job, |status| report(index, status)).await
+            };
+
+            report(
+                index,
+                match result {
+                    Ok(()) => JobStatus::Succeeded,
+                    Err(e) => JobStatus::Failed(format!("{:#}", e)),
+                },
+            );
+        })
+        .await;
+}
+
+async fn run_job(
+    client: &GitHubClient,
+    backup: Option<&Backup>,
+    job: &Job,
+    report: impl Fn(JobStatus),
+) -> Result<()> {
+    if job.action == RepoAction::Delete {
+        if let Some(backup) = backup {
+            report(JobStatus::BackingUp);
+            backup
+                .run(client, &job.repo)
+                .await
+                .wrap_err("backup failed")?;
+        }
+    }
+    report(JobStatus::Running);
+    apply(client, &job.repo.full_name, job.action).await
+}
@@ -0,0 +1,87 @@
+use chrono::Duration;
+
+use crate::{
+    fuzzy::FuzzyMatcher,
+    github::types::{parse_timestamp, Repository},
+    query::{self, Query},
+    state::SortMode,
+};
+
+/// Parse a staleness threshold such as `6m` or `2y` from the command line or
+/// config file.
+pub fn parse_stale(s: &str) -> Result<Duration, String> {
+    query::parse_age(s)
+}
+
+/// Everything that decides which repos are listed and in what order. Shared
+/// by the TUI and the headless subcommands so both select the same repos.
+#[derive(Debug, Clone, Default)]
+pub struct RepoFilter {
+    pub query: String,
+    pub private_only: bool,
+    pub public_only: bool,
+    pub forks_only: bool,
+    pub archived_only: bool,
+    pub empty_only: bool,
+    /// Only repos not updated within this long.
+    pub stale: Option<Duration>,
+    pub owner: Option<String>,
+    pub sort: SortMode,
+}
+
+impl RepoFilter {
+    pub fn matches(&self, repo: &Repository) -> bool {
+        if self.private_only && !repo.private {
+            return false;
+        }
+        if self.public_only && repo.private {
+            return false;
+        }
+        if self.forks_only && !repo.fork {
+            return false;
+        }
+        if self.archived_only && !repo.archived {
+            return false;
+        }
+        if self.empty_only && repo.size_kb > 0 {
+            return false;
+        }
+        if let Some(stale) = self.stale {
+            let updated = repo.updated_at.as_deref().and_then(parse_timestamp);
+            if updated.is_some_and(|at| chrono::Utc::now() - at < stale) {
+                return false;
+            }
+        }
+        if let Some(owner) = &self.owner {
+            if &repo.owner != owner {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Indices into `repos` of every matching repo, in display order. Filter
+    /// tokens in the query are applied before fuzzy matching the rest.
+    pub fn apply(&self, fuzzy: &mut FuzzyMatcher, repos: &[Repository]) -> Vec<usize> {
+        let query = Query::parse(&self.query);
+        let mut indices = fuzzy.filter(repos, &query.text);
+        indices.retain(|&idx| self.matches(&repos[idx]) && query.matches(&repos[idx]));
+
+        match self.sort {
+            SortMode::LastUpdated => {
+                indices.sort_by(|&a, &b| repos[b].updated_at.cmp(&repos[a].updated_at));
+            }
+            SortMode::Created => {
+                indices.sort_by(|&a, &b| repos[b].created_at.cmp(&repos[a].created_at));
+            }
+            SortMode::Name => {
+                indices.sort_by_key(|&idx| repos[idx].full_name.to_lowercase());
+            }
+            SortMode::Stars => {
+                indices.sort_by_key(|&idx| std::cmp::Reverse(repos[idx].stargazers_count));
+            }
+        }
+
+        indices
+    }
+}
@@ -34,6 +34,7 @@ impl FuzzyMatcher {
             })
             .collect();
 
+        #[allow(clippy::unnecessary_sort_by)]
         scored.sort_by(|a, b| b.1.cmp(&a.1));
         scored.into_iter().map(|(idx, _)| idx).collect()
     }
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use super::host::Host;
+use super::types::Repository;
+
+/// One page of a repository listing as last downloaded, with the `ETag`
+/// GitHub sent for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedPage {
+    pub etag: String,
+    /// Repositories GitHub returned on the page, before any filtering; an
+    /// empty page ends the listing.
+    pub count: usize,
+    pub repos: Vec<Repository>,
+}
+
+/// Listing pages keyed by request URL, used to send `If-None-Match` so
+/// unchanged pages come back as a cheap `304 Not Modified`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageCache {
+    pages: HashMap<String, CachedPage>,
+}
+
+impl PageCache {
+    pub fn get(&self, url: &str) -> Option<&CachedPage> {
+        self.pages.get(url)
+    }
+
+    pub fn insert(&mut self, url: String, page: CachedPage) {
+        self.pages.insert(url, page);
+    }
+}
+
+/// The repository list from the last refresh, kept on disk so the TUI can
+/// show it immediately on the next launch.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepoCache {
+    pub fetched_at: Option<DateTime<Utc>>,
+    pub repos: Vec<Repository>,
+    pub pages: PageCache,
+}
+
+impl RepoCache {
+    /// Cache file for the account `login` on `host`. It's keyed by account
+    /// rather than profile, since a token from the environment, stdin or the
+    /// gh CLI can log a profile in as someone else.
+    pub fn path(host: &Host, login: &str) -> Result<PathBuf> {
+        Ok(dirs::cache_dir()
+            .ok_or_else(|| eyre!("Could not find cache directory"))?
+            .join("ghune")
+            .join(host.name().replace(':', "_"))
+            .join(format!("{}.json", login.to_lowercase())))
+    }
+
+    /// The cached list, or `None` if there isn't a usable one. A cache
+    /// written by an older version is simply ignored.
+    pub fn load(path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Write the cache readable only by the owner, since it lists private
+    /// repos. It goes to a temporary file first and is renamed into place,
+    /// so a crash never leaves a truncated cache behind.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        // A leftover from a crash may have other permissions; start afresh.
+        let _ = fs::remove_file(&tmp);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&tmp)?;
+        file.write_all(serde_json::to_string(self)?.as_bytes())?;
+        file.sync_all()?;
+
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
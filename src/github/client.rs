@@ -1,44 +1,295 @@
-use color_eyre::eyre::Result;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::eyre::{eyre, Result};
+use http::header::{HeaderValue, ETAG, IF_NONE_MATCH, LINK};
+use http::{HeaderMap, StatusCode};
 use octocrab::Octocrab;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::cache::{CachedPage, PageCache};
+use super::host::Host;
+use super::rate_limit::{retry_delay, RateLimitTracker};
+use super::types::{RepoDetails, Repository, TokenInfo, TokenScopes};
+
+/// How many times a rate-limited request is retried before giving up.
+const MAX_RETRIES: u32 = 3;
+
+/// The longest rate limit ghune waits out; past this the request fails
+/// instead of hanging until the window resets.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
 
-use super::types::Repository;
+#[derive(Clone, Copy)]
+enum Method {
+    Get,
+    Patch,
+    Delete,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+}
 
+#[derive(Deserialize)]
+struct ReadmeBody {
+    content: String,
+}
+
+#[derive(Serialize)]
+struct ArchiveRequest {
+    archived: bool,
+}
+
+/// Result of listing every repository the user can manage.
+#[derive(Debug, Clone)]
+pub struct RepoListing {
+    pub repos: Vec<Repository>,
+    /// Organizations whose repositories couldn't be listed.
+    pub org_errors: Vec<String>,
+    /// Pages seen during this listing, to revalidate against next time.
+    pub pages: PageCache,
+}
+
+#[derive(Clone)]
 pub struct GitHubClient {
     octocrab: Octocrab,
+    rate_limit: RateLimitTracker,
+    host: Host,
 }
 
 impl GitHubClient {
-    pub async fn new(token: &str) -> Result<Self> {
+    pub async fn new(token: &str, host: &Host) -> Result<Self> {
         let octocrab = Octocrab::builder()
+            .base_uri(host.api_url())?
             .personal_token(token.to_string())
             .build()?;
 
-        Ok(Self { octocrab })
+        Ok(Self {
+            octocrab,
+            rate_limit: RateLimitTracker::default(),
+            host: host.clone(),
+        })
     }
 
-    pub async fn list_repos(&self) -> Result<Vec<Repository>> {
-        let mut repos = Vec::new();
+    /// Convert an API repository, pointing `html_url` at this client's host
+    /// when the API left it out.
+    fn repository(&self, repo: octocrab::models::Repository) -> Repository {
+        let mut repo = Repository::from(repo);
+        if repo.html_url.is_empty() {
+            repo.html_url = format!("{}/{}", self.host.web_url(), repo.full_name);
+        }
+        repo
+    }
+
+    pub fn rate_limit(&self) -> &RateLimitTracker {
+        &self.rate_limit
+    }
+
+    pub async fn current_user(&self) -> Result<String> {
+        let user: octocrab::models::Author = self.get_json("/user").await?;
+        Ok(user.login)
+    }
+
+    /// Who the token belongs to and what it may do, or `None` if GitHub
+    /// rejects it outright (expired or revoked).
+    pub async fn token_info(&self) -> Result<Option<TokenInfo>> {
+        let (status, headers, body) = self.send(Method::Get, "/user", None::<&()>, None).await?;
+        if status == StatusCode::UNAUTHORIZED {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(api_error(status, &body));
+        }
+
+        let user: octocrab::models::Author = serde_json::from_str(&body)?;
+        // Only classic and OAuth tokens report scopes; fine-grained tokens
+        // carry per-repository permissions instead.
+        let scopes = match headers.get("x-oauth-scopes") {
+            Some(value) => TokenScopes::Classic(
+                value
+                    .to_str()
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|scope| scope.trim().to_string())
+                    .filter(|scope| !scope.is_empty())
+                    .collect(),
+            ),
+            None => TokenScopes::FineGrained,
+        };
+        Ok(Some(TokenInfo {
+            login: user.login,
+            scopes,
+        }))
+    }
+
+    pub async fn list_orgs(&self) -> Result<Vec<String>> {
+        let orgs: Vec<octocrab::models::orgs::Organization> =
+            self.paginate("/user/orgs", &[]).await?;
+        Ok(orgs.into_iter().map(|org| org.login).collect())
+    }
+
+    pub async fn list_repos(
+        &self,
+        cache: &PageCache,
+        fresh: &mut PageCache,
+        on_page: &mut (dyn FnMut(&[Repository]) + Send),
+    ) -> Result<Vec<Repository>> {
+        self.paginate_repos(
+            "/user/repos",
+            &[("affiliation", "owner"), ("sort", "updated")],
+            cache,
+            fresh,
+            |_| true,
+            on_page,
+        )
+        .await
+    }
+
+    /// Fetch personal repos plus those of every organization the user
+    /// administers. Organization failures are collected rather than fatal so
+    /// a single inaccessible org doesn't hide everything else. Pages that
+    /// haven't changed since `cache` was filled are reused. `on_page` sees
+    /// each page's repos as it arrives, for showing partial results.
+    pub async fn list_all_repos(
+        &self,
+        cache: &PageCache,
+        on_page: &mut (dyn FnMut(&[Repository]) + Send),
+    ) -> Result<RepoListing> {
+        let mut pages = PageCache::default();
+        let mut repos = self.list_repos(cache, &mut pages, on_page).await?;
+        let mut org_errors = Vec::new();
+
+        match self.list_orgs().await {
+            Ok(orgs) => {
+                for org in orgs {
+                    match self.list_org_repos(&org, cache, &mut pages, on_page).await {
+                        Ok(org_repos) => repos.extend(org_repos),
+                        Err(e) => org_errors.push(format!("{}: {}", org, e)),
+                    }
+                }
+            }
+            Err(e) => org_errors.push(format!("listing orgs: {}", e)),
+        }
+
+        Ok(RepoListing {
+            repos,
+            org_errors,
+            pages,
+        })
+    }
+
+    /// Repositories in `org` that the authenticated user administers, i.e.
+    /// the ones they are actually allowed to delete or archive.
+    pub async fn list_org_repos(
+        &self,
+        org: &str,
+        cache: &PageCache,
+        fresh: &mut PageCache,
+        on_page: &mut (dyn FnMut(&[Repository]) + Send),
+    ) -> Result<Vec<Repository>> {
+        let route = format!("/orgs/{}/repos", org);
+        self.paginate_repos(
+            &route,
+            &[("type", "all"), ("sort", "updated")],
+            cache,
+            fresh,
+            |repo| repo.permissions.as_ref().is_some_and(|p| p.admin),
+            on_page,
+        )
+        .await
+    }
+
+    pub async fn list_issues_json(&self, full_name: &str) -> Result<Vec<serde_json::Value>> {
+        let route = format!("/repos/{}/issues", full_name);
+        self.paginate(&route, &[("state", "all")]).await
+    }
+
+    pub async fn list_pulls_json(&self, full_name: &str) -> Result<Vec<serde_json::Value>> {
+        let route = format!("/repos/{}/pulls", full_name);
+        self.paginate(&route, &[("state", "all")]).await
+    }
+
+    async fn paginate<T: DeserializeOwned>(
+        &self,
+        route: &str,
+        params: &[(&str, &str)],
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
         let mut page = 1u32;
 
         loop {
-            let response: Vec<octocrab::models::Repository> = self
-                .octocrab
-                .get(
-                    "/user/repos",
-                    Some(&[
-                        ("per_page", "100"),
-                        ("page", &page.to_string()),
-                        ("affiliation", "owner"),
-                        ("sort", "updated"),
-                    ]),
-                )
-                .await?;
+            let response: Vec<T> = self.get_json(&page_url(route, params, page)).await?;
 
             if response.is_empty() {
                 break;
             }
 
-            repos.extend(response.into_iter().map(Repository::from));
+            items.extend(response);
+            page += 1;
+        }
+
+        Ok(items)
+    }
+
+    /// Like `paginate`, for repository listings: each page is revalidated
+    /// against `cache` with `If-None-Match`, so an unchanged page costs a
+    /// `304 Not Modified` (which doesn't count against the rate limit)
+    /// instead of a download. Every page is recorded in `fresh` and passed
+    /// to `on_page`; repos that fail `keep` are dropped before caching.
+    async fn paginate_repos(
+        &self,
+        route: &str,
+        params: &[(&str, &str)],
+        cache: &PageCache,
+        fresh: &mut PageCache,
+        keep: fn(&octocrab::models::Repository) -> bool,
+        on_page: &mut (dyn FnMut(&[Repository]) + Send),
+    ) -> Result<Vec<Repository>> {
+        let mut repos = Vec::new();
+        let mut page = 1u32;
+
+        loop {
+            let url = page_url(route, params, page);
+            let cached = cache.get(&url);
+            let mut headers = HeaderMap::new();
+            if let Some(etag) = cached.and_then(|c| HeaderValue::from_str(&c.etag).ok()) {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+
+            let (status, response_headers, body) = self
+                .send(Method::Get, &url, None::<&()>, Some(headers))
+                .await?;
+            let page_data = match cached {
+                Some(cached) if status == StatusCode::NOT_MODIFIED => cached.clone(),
+                _ if status.is_success() => {
+                    let listed: Vec<octocrab::models::Repository> = serde_json::from_str(&body)?;
+                    CachedPage {
+                        etag: response_headers
+                            .get(ETAG)
+                            .and_then(|v| v.to_str().ok())
+                            .unwrap_or_default()
+                            .to_string(),
+                        count: listed.len(),
+                        repos: listed
+                            .into_iter()
+                            .filter(keep)
+                            .map(|repo| self.repository(repo))
+                            .collect(),
+                    }
+                }
+                _ => return Err(api_error(status, &body)),
+            };
+
+            let last = page_data.count == 0;
+            if !page_data.repos.is_empty() {
+                on_page(&page_data.repos);
+            }
+            repos.extend(page_data.repos.iter().cloned());
+            fresh.insert(url, page_data);
+            if last {
+                break;
+            }
             page += 1;
         }
 
@@ -47,7 +298,374 @@ impl GitHubClient {
 
     pub async fn delete_repo(&self, full_name: &str) -> Result<()> {
         let url = format!("/repos/{}", full_name);
-        self.octocrab._delete(&url, None::<&()>).await?;
+        self.request(Method::Delete, &url, None::<&()>).await?;
+        Ok(())
+    }
+
+    pub async fn set_archived(&self, full_name: &str, archived: bool) -> Result<()> {
+        let url = format!("/repos/{}", full_name);
+        self.request(Method::Patch, &url, Some(&ArchiveRequest { archived }))
+            .await?;
         Ok(())
     }
+
+    /// Fork parent, watcher count and open pull request count. Pull
+    /// requests are listed one per page so the count comes from the `Link`
+    /// header without downloading them.
+    pub async fn repo_details(&self, full_name: &str) -> Result<RepoDetails> {
+        let repo: octocrab::models::Repository =
+            self.get_json(&format!("/repos/{}", full_name)).await?;
+
+        let route = format!("/repos/{}/pulls?state=open&per_page=1", full_name);
+        let (status, headers, body) = self.send(Method::Get, &route, None::<&()>, None).await?;
+        if !status.is_success() {
+            return Err(api_error(status, &body));
+        }
+        let open_pulls = match last_page(&headers) {
+            Some(pages) => pages,
+            None => serde_json::from_str::<Vec<serde_json::Value>>(&body)?.len(),
+        };
+
+        Ok(RepoDetails {
+            parent: repo.parent.and_then(|p| p.full_name),
+            subscribers: repo.subscribers_count.unwrap_or(0).max(0) as u32,
+            open_pulls,
+        })
+    }
+
+    /// The repo's README, or `None` if it doesn't have one.
+    pub async fn readme(&self, full_name: &str) -> Result<Option<String>> {
+        let route = format!("/repos/{}/readme", full_name);
+        let (status, _, body) = self.send(Method::Get, &route, None::<&()>, None).await?;
+        match status {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let readme: ReadmeBody = serde_json::from_str(&body)?;
+                let bytes = STANDARD.decode(readme.content.replace('\n', ""))?;
+                Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+            }
+            status => Err(api_error(status, &body)),
+        }
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, route: &str) -> Result<T> {
+        let body = self.request(Method::Get, route, None::<&()>).await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Send a request and return its body, failing on any non-success status.
+    async fn request<B: Serialize + ?Sized>(
+        &self,
+        method: Method,
+        route: &str,
+        body: Option<&B>,
+    ) -> Result<String> {
+        let (status, _, text) = self.send(method, route, body, None).await?;
+        if status.is_success() {
+            Ok(text)
+        } else {
+            Err(api_error(status, &text))
+        }
+    }
+
+    /// Send a request and return its final status, headers and body, recording rate
+    /// limit headers and waiting out primary and secondary rate limits before
+    /// retrying. A limit that won't reset within `MAX_RETRY_DELAY` is an error.
+    /// `headers` are added to GET requests only.
+    async fn send<B: Serialize + ?Sized>(
+        &self,
+        method: Method,
+        route: &str,
+        body: Option<&B>,
+        headers: Option<HeaderMap>,
+    ) -> Result<(StatusCode, HeaderMap, String)> {
+        let mut attempt = 0;
+        loop {
+            let response = match method {
+                Method::Get => {
+                    self.octocrab
+                        ._get_with_headers(route, headers.clone())
+                        .await?
+                }
+                Method::Patch => self.octocrab._patch(route, body).await?,
+                Method::Delete => self.octocrab._delete(route, body).await?,
+            };
+            self.rate_limit.update(response.headers());
+
+            let status = response.status();
+            let headers = response.headers().clone();
+            let text = self.octocrab.body_to_string(response).await?;
+
+            match retry_delay(status, &headers, &text) {
+                Some(delay) if delay > MAX_RETRY_DELAY => {
+                    return Err(eyre!(
+                        "GitHub rate limit exceeded; it resets in {} min",
+                        delay.as_secs().div_ceil(60)
+                    ));
+                }
+                Some(delay) if attempt < MAX_RETRIES => {
+                    attempt += 1;
+                    self.rate_limit.begin_backoff(delay);
+                    tokio::time::sleep(delay).await;
+                    self.rate_limit.end_backoff();
+                }
+                _ => return Ok((status, headers, text)),
+            }
+        }
+    }
+}
+
+/// The page number of the `rel="last"` link in a `Link` header.
+fn last_page(headers: &HeaderMap) -> Option<usize> {
+    let link = headers.get(LINK)?.to_str().ok()?;
+    let last = link.split(',').find(|part| part.contains("rel=\"last\""))?;
+    let url = last.split(';').next()?.trim();
+    url.trim_start_matches('<')
+        .trim_end_matches('>')
+        .split(['?', '&'])
+        .find_map(|param| param.strip_prefix("page="))?
+        .parse()
+        .ok()
+}
+
+fn page_url(route: &str, params: &[(&str, &str)], page: u32) -> String {
+    let mut url = format!("{}?per_page=100&page={}", route, page);
+    for (key, value) in params {
+        url.push_str(&format!("&{}={}", key, value));
+    }
+    url
+}
+
+fn api_error(status: StatusCode, body: &str) -> color_eyre::Report {
+    let message = serde_json::from_str::<ErrorBody>(body)
+        .map(|e| e.message)
+        .unwrap_or_else(|_| body.trim().to_string());
+    eyre!("GitHub API error ({}): {}", status, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn repo_json(name: &str, html_url: Option<&str>) -> serde_json::Value {
+        let mut repo = serde_json::json!({
+            "id": 1,
+            "name": name,
+            "full_name": format!("octo/{}", name),
+            "owner": {
+                "login": "octo",
+                "id": 1,
+                "node_id": "",
+                "avatar_url": "https://ghe.test/avatars/octo",
+                "gravatar_id": "",
+                "url": "https://ghe.test/api/v3/users/octo",
+                "html_url": "https://ghe.test/octo",
+                "followers_url": "https://ghe.test/api/v3/users/octo/followers",
+                "following_url": "https://ghe.test/api/v3/users/octo/following",
+                "gists_url": "https://ghe.test/api/v3/users/octo/gists",
+                "starred_url": "https://ghe.test/api/v3/users/octo/starred",
+                "subscriptions_url": "https://ghe.test/api/v3/users/octo/subscriptions",
+                "organizations_url": "https://ghe.test/api/v3/users/octo/orgs",
+                "repos_url": "https://ghe.test/api/v3/users/octo/repos",
+                "events_url": "https://ghe.test/api/v3/users/octo/events",
+                "received_events_url": "https://ghe.test/api/v3/users/octo/received_events",
+                "type": "User",
+                "site_admin": false,
+            },
+            "url": format!("https://ghe.test/api/v3/repos/octo/{}", name),
+        });
+        if let Some(url) = html_url {
+            repo["html_url"] = url.into();
+        }
+        repo
+    }
+
+    /// A GHES instance only answers under `/api/v3`, like the real thing.
+    async fn enterprise_server() -> (MockServer, GitHubClient) {
+        let server = MockServer::start().await;
+        let host = Host::parse(&server.uri()).unwrap();
+        let client = GitHubClient::new("token", &host).await.unwrap();
+        (server, client)
+    }
+
+    #[tokio::test]
+    async fn lists_repos_under_api_v3_prefix() {
+        let (server, client) = enterprise_server().await;
+        let listed = serde_json::json!([
+            repo_json("kept", Some("https://ghe.test/octo/kept")),
+            repo_json("bare", None),
+        ]);
+        Mock::given(method("GET"))
+            .and(path("/api/v3/user/repos"))
+            .and(query_param("page", "1"))
+            .and(header("authorization", "Bearer token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(listed))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/user/repos"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(&server)
+            .await;
+
+        let repos = client
+            .list_repos(
+                &PageCache::default(),
+                &mut PageCache::default(),
+                &mut |_| {},
+            )
+            .await
+            .unwrap();
+        let urls: Vec<_> = repos.iter().map(|r| r.html_url.as_str()).collect();
+        let fallback = format!("{}/octo/bare", server.uri());
+        assert_eq!(urls, vec!["https://ghe.test/octo/kept", fallback.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn revalidates_cached_pages() {
+        let (server, client) = enterprise_server().await;
+        let listed = serde_json::json!([repo_json("kept", None)]);
+        Mock::given(method("GET"))
+            .and(path("/api/v3/user/repos"))
+            .and(query_param("page", "1"))
+            .and(header("if-none-match", "\"one\""))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/user/repos"))
+            .and(query_param("page", "1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"one\"")
+                    .set_body_json(listed),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/user/repos"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(&server)
+            .await;
+
+        let mut cache = PageCache::default();
+        let first = client
+            .list_repos(&PageCache::default(), &mut cache, &mut |_| {})
+            .await
+            .unwrap();
+        let second = client
+            .list_repos(&cache, &mut PageCache::default(), &mut |_| {})
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(second[0].full_name, first[0].full_name);
+    }
+
+    #[tokio::test]
+    async fn checks_token_under_api_v3_prefix() {
+        let (server, client) = enterprise_server().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/user"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-oauth-scopes", "repo, read:org")
+                    .set_body_json(repo_json("x", None)["owner"].clone()),
+            )
+            .mount(&server)
+            .await;
+
+        let info = client.token_info().await.unwrap().unwrap();
+        assert_eq!(info.login, "octo");
+        assert_eq!(
+            info.scopes,
+            TokenScopes::Classic(vec!["repo".into(), "read:org".into()])
+        );
+        assert_eq!(
+            info.deletion_blocker(),
+            Some("token lacks the delete_repo scope")
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_rejected_token() {
+        let (server, client) = enterprise_server().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/user"))
+            .respond_with(
+                ResponseTemplate::new(401)
+                    .set_body_json(serde_json::json!({ "message": "Bad credentials" })),
+            )
+            .mount(&server)
+            .await;
+
+        assert!(client.token_info().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn counts_open_pulls_from_link_header() {
+        let (server, client) = enterprise_server().await;
+        let mut repo = repo_json("kept", None);
+        repo["subscribers_count"] = 3.into();
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/octo/kept"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(repo))
+            .mount(&server)
+            .await;
+        let last = format!(
+            "<{}/api/v3/repositories/1/pulls?state=open&per_page=1&page=2>; rel=\"next\", \
+             <{}/api/v3/repositories/1/pulls?state=open&per_page=1&page=42>; rel=\"last\"",
+            server.uri(),
+            server.uri()
+        );
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/octo/kept/pulls"))
+            .and(query_param("per_page", "1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("link", last.as_str())
+                    .set_body_json(serde_json::json!([{}])),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/octo/kept/readme"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let details = client.repo_details("octo/kept").await.unwrap();
+        assert_eq!(details.open_pulls, 42);
+        assert_eq!(details.subscribers, 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_distant_rate_limit_reset() {
+        let (server, client) = enterprise_server().await;
+        let reset = chrono::Utc::now().timestamp() + 3600;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/user"))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .insert_header("x-ratelimit-limit", "5000")
+                    .insert_header("x-ratelimit-remaining", "0")
+                    .insert_header("x-ratelimit-reset", reset.to_string().as_str())
+                    .set_body_json(serde_json::json!({ "message": "API rate limit exceeded" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let err = client.token_info().await.unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("GitHub rate limit exceeded; it resets in"));
+        assert_eq!(client.rate_limit.backoff_remaining(), None);
+    }
 }
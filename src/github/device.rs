@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use color_eyre::eyre::{bail, Result};
+use http::header::ACCEPT;
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+
+/// Scopes requested by `ghune login`, matching what the token prompt asks for.
+pub const LOGIN_SCOPES: &str = "repo delete_repo read:org";
+
+/// Extra wait GitHub asks for each time it answers `slow_down`.
+const SLOW_DOWN_STEP: Duration = Duration::from_secs(5);
+
+/// The codes from the first step of the device flow: the user enters
+/// `user_code` at `verification_uri` while ghune polls with `device_code`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Seconds until the codes expire.
+    pub expires_in: u64,
+    /// Minimum seconds between polls.
+    pub interval: u64,
+}
+
+#[derive(Serialize)]
+struct CodeRequest<'a> {
+    client_id: &'a str,
+    scope: &'a str,
+}
+
+#[derive(Serialize)]
+struct TokenRequest<'a> {
+    client_id: &'a str,
+    device_code: &'a str,
+    grant_type: &'a str,
+}
+
+/// GitHub answers every poll with 200; the body says whether the user has
+/// finished authorizing.
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+    interval: Option<u64>,
+}
+
+/// GitHub's OAuth device authorization flow, for logging in from a terminal
+/// without creating a token by hand.
+pub struct DeviceFlow {
+    octocrab: Octocrab,
+    client_id: String,
+}
+
+impl DeviceFlow {
+    /// `base_url` is the web host serving `/login/device/code` and
+    /// `/login/oauth/access_token`, e.g. `https://github.com`.
+    pub fn new(base_url: &str, client_id: &str) -> Result<Self> {
+        let octocrab = Octocrab::builder()
+            .base_uri(base_url)?
+            .add_header(ACCEPT, "application/json".to_string())
+            .build()?;
+        Ok(Self {
+            octocrab,
+            client_id: client_id.to_string(),
+        })
+    }
+
+    pub async fn start(&self, scope: &str) -> Result<DeviceCode> {
+        let code = self
+            .octocrab
+            .post(
+                "/login/device/code",
+                Some(&CodeRequest {
+                    client_id: &self.client_id,
+                    scope,
+                }),
+            )
+            .await?;
+        Ok(code)
+    }
+
+    /// Poll until the user authorizes or denies the request, or the code
+    /// expires, and return the access token.
+    pub async fn poll(&self, code: &DeviceCode) -> Result<String> {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(code.expires_in);
+        let mut interval = Duration::from_secs(code.interval);
+
+        loop {
+            tokio::time::sleep(interval).await;
+            if tokio::time::Instant::now() > deadline {
+                bail!("The login code expired; run `ghune login` again");
+            }
+
+            let response: TokenResponse = self
+                .octocrab
+                .post(
+                    "/login/oauth/access_token",
+                    Some(&TokenRequest {
+                        client_id: &self.client_id,
+                        device_code: &code.device_code,
+                        grant_type: "urn:ietf:params:oauth:grant-type:device_code",
+                    }),
+                )
+                .await?;
+
+            if let Some(token) = response.access_token {
+                return Ok(token);
+            }
+            match response.error.as_deref() {
+                Some("authorization_pending") => {}
+                Some("slow_down") => {
+                    interval = response
+                        .interval
+                        .map(Duration::from_secs)
+                        .unwrap_or(interval + SLOW_DOWN_STEP);
+                }
+                Some("expired_token") => {
+                    bail!("The login code expired; run `ghune login` again")
+                }
+                Some("access_denied") => bail!("Login was cancelled in the browser"),
+                Some(error) => bail!(
+                    "Login failed: {}",
+                    response.error_description.as_deref().unwrap_or(error)
+                ),
+                None => bail!("Login failed: GitHub returned neither a token nor an error"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn server_with_codes() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/login/device/code"))
+            .and(body_partial_json(serde_json::json!({
+                "client_id": "client",
+                "scope": LOGIN_SCOPES,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "device_code": "device",
+                "user_code": "ABCD-1234",
+                "verification_uri": "https://github.com/login/device",
+                "expires_in": 900,
+                "interval": 0,
+            })))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn token_poll(body: serde_json::Value) -> Mock {
+        Mock::given(method("POST"))
+            .and(path("/login/oauth/access_token"))
+            .and(body_partial_json(serde_json::json!({
+                "client_id": "client",
+                "device_code": "device",
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+    }
+
+    #[tokio::test]
+    async fn polls_until_authorized() {
+        let server = server_with_codes().await;
+        token_poll(serde_json::json!({ "error": "authorization_pending" }))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        token_poll(serde_json::json!({
+            "access_token": "gho_token",
+            "token_type": "bearer",
+            "scope": "repo,delete_repo,read:org",
+        }))
+        .mount(&server)
+        .await;
+
+        let flow = DeviceFlow::new(&server.uri(), "client").unwrap();
+        let code = flow.start(LOGIN_SCOPES).await.unwrap();
+        assert_eq!(code.user_code, "ABCD-1234");
+        assert_eq!(flow.poll(&code).await.unwrap(), "gho_token");
+
+        let polls = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|r| r.url.path() == "/login/oauth/access_token")
+            .count();
+        assert_eq!(polls, 3);
+    }
+
+    #[tokio::test]
+    async fn reports_denied_login() {
+        let server = server_with_codes().await;
+        token_poll(serde_json::json!({ "error": "access_denied" }))
+            .mount(&server)
+            .await;
+
+        let flow = DeviceFlow::new(&server.uri(), "client").unwrap();
+        let code = flow.start(LOGIN_SCOPES).await.unwrap();
+        let err = flow.poll(&code).await.unwrap_err();
+        assert!(err.to_string().contains("cancelled"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn reports_expired_code() {
+        let server = server_with_codes().await;
+        token_poll(serde_json::json!({ "error": "expired_token" }))
+            .mount(&server)
+            .await;
+
+        let flow = DeviceFlow::new(&server.uri(), "client").unwrap();
+        let code = flow.start(LOGIN_SCOPES).await.unwrap();
+        let err = flow.poll(&code).await.unwrap_err();
+        assert!(err.to_string().contains("expired"), "{}", err);
+    }
+}
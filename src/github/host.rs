@@ -0,0 +1,101 @@
+use std::fmt;
+
+use color_eyre::eyre::{eyre, Result};
+
+/// Name of the public GitHub instance.
+pub const GITHUB_COM: &str = "github.com";
+
+/// The GitHub instance ghune talks to: github.com or a GitHub Enterprise
+/// Server, whose REST API lives under `/api/v3` on the same host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Host {
+    /// Scheme and authority without a trailing slash, e.g.
+    /// `https://github.example.com`.
+    web_url: String,
+}
+
+impl Default for Host {
+    fn default() -> Self {
+        Self {
+            web_url: format!("https://{}", GITHUB_COM),
+        }
+    }
+}
+
+impl Host {
+    /// Parse `github.example.com` or a URL such as
+    /// `https://github.example.com/`; `https` is assumed without a scheme.
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim().trim_end_matches('/');
+        let web_url = if s.contains("://") {
+            s.to_string()
+        } else {
+            format!("https://{}", s)
+        };
+        let (_, authority) = web_url.split_once("://").unwrap_or_default();
+        if authority.is_empty() || authority.contains('/') {
+            return Err(eyre!(
+                "Invalid host '{}': expected a host name like github.example.com",
+                s
+            ));
+        }
+        Ok(Self { web_url })
+    }
+
+    /// Host name (and port, if any) without the scheme.
+    pub fn name(&self) -> &str {
+        self.web_url
+            .split_once("://")
+            .map_or(&self.web_url, |(_, name)| name)
+    }
+
+    pub fn is_github_com(&self) -> bool {
+        self.name().eq_ignore_ascii_case(GITHUB_COM)
+    }
+
+    pub fn web_url(&self) -> &str {
+        &self.web_url
+    }
+
+    pub fn api_url(&self) -> String {
+        if self.is_github_com() {
+            format!("https://api.{}", GITHUB_COM)
+        } else {
+            format!("{}/api/v3", self.web_url)
+        }
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn github_com_uses_api_subdomain() {
+        let host = Host::default();
+        assert!(host.is_github_com());
+        assert_eq!(host.api_url(), "https://api.github.com");
+        assert_eq!(Host::parse("github.com").unwrap(), host);
+    }
+
+    #[test]
+    fn enterprise_uses_api_v3_prefix() {
+        let host = Host::parse("https://ghe.example.com/").unwrap();
+        assert_eq!(host.name(), "ghe.example.com");
+        assert_eq!(host.web_url(), "https://ghe.example.com");
+        assert_eq!(host.api_url(), "https://ghe.example.com/api/v3");
+        assert_eq!(Host::parse("ghe.example.com").unwrap(), host);
+    }
+
+    #[test]
+    fn rejects_paths() {
+        assert!(Host::parse("ghe.example.com/api/v3").is_err());
+        assert!(Host::parse("").is_err());
+    }
+}
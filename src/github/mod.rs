@@ -1,4 +1,9 @@
+pub mod cache;
 pub mod client;
+pub mod device;
+pub mod host;
+pub mod rate_limit;
 pub mod types;
 
 pub use client::GitHubClient;
+pub use host::Host;
@@ -0,0 +1,171 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use http::{HeaderMap, StatusCode};
+
+/// How long to back off from a secondary rate limit that doesn't say how long
+/// to wait, per GitHub's guidance.
+const SECONDARY_LIMIT_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Primary rate limit quota as reported by the last `X-RateLimit-*` headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Unix timestamp (seconds) at which `remaining` resets to `limit`.
+    pub reset: i64,
+}
+
+impl RateLimit {
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        Some(Self {
+            limit: header(headers, "x-ratelimit-limit")?,
+            remaining: header(headers, "x-ratelimit-remaining")?,
+            reset: header(headers, "x-ratelimit-reset")?,
+        })
+    }
+
+    pub fn resets_in(&self) -> Duration {
+        let secs = self.reset - chrono::Utc::now().timestamp();
+        Duration::from_secs(secs.max(0) as u64)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    limit: Option<RateLimit>,
+    backoff_until: Option<Instant>,
+}
+
+/// Rate limit state shared by every clone of a `GitHubClient`, so requests
+/// made from background jobs are reflected in the header.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitTracker {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl RateLimitTracker {
+    pub fn current(&self) -> Option<RateLimit> {
+        self.inner.lock().unwrap().limit
+    }
+
+    /// Time left until a request that hit a rate limit is retried.
+    pub fn backoff_remaining(&self) -> Option<Duration> {
+        let until = self.inner.lock().unwrap().backoff_until?;
+        until.checked_duration_since(Instant::now())
+    }
+
+    pub(super) fn update(&self, headers: &HeaderMap) {
+        if let Some(limit) = RateLimit::from_headers(headers) {
+            self.inner.lock().unwrap().limit = Some(limit);
+        }
+    }
+
+    pub(super) fn begin_backoff(&self, delay: Duration) {
+        self.inner.lock().unwrap().backoff_until = Some(Instant::now() + delay);
+    }
+
+    pub(super) fn end_backoff(&self) {
+        self.inner.lock().unwrap().backoff_until = None;
+    }
+}
+
+/// How long to wait before retrying a rate-limited response, or `None` if the
+/// response wasn't rate limited. `body` is only consulted for a 403 without
+/// rate limit headers, which is how GitHub reports some secondary limits.
+pub(super) fn retry_delay(status: StatusCode, headers: &HeaderMap, body: &str) -> Option<Duration> {
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+
+    if let Some(secs) = header::<u64>(headers, "retry-after") {
+        return Some(Duration::from_secs(secs));
+    }
+
+    if let Some(limit) = RateLimit::from_headers(headers) {
+        if limit.remaining == 0 {
+            // Round up so we don't wake a moment before the window resets.
+            return Some(limit.resets_in() + Duration::from_secs(1));
+        }
+    }
+
+    if status == StatusCode::TOO_MANY_REQUESTS || body.contains("secondary rate limit") {
+        return Some(SECONDARY_LIMIT_BACKOFF);
+    }
+
+    None
+}
+
+fn header<T: std::str::FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, String)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (http::HeaderName::from_static(name), value.parse().unwrap()))
+            .collect()
+    }
+
+    fn exhausted(reset_in: i64) -> HeaderMap {
+        let reset = chrono::Utc::now().timestamp() + reset_in;
+        headers(&[
+            ("x-ratelimit-limit", "5000".into()),
+            ("x-ratelimit-remaining", "0".into()),
+            ("x-ratelimit-reset", reset.to_string()),
+        ])
+    }
+
+    #[test]
+    fn waits_for_primary_limit_reset() {
+        let delay = retry_delay(StatusCode::FORBIDDEN, &exhausted(30), "").unwrap();
+        assert!((30..=31).contains(&delay.as_secs()), "{:?}", delay);
+
+        let delay = retry_delay(StatusCode::TOO_MANY_REQUESTS, &exhausted(-5), "").unwrap();
+        assert_eq!(delay, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn honours_retry_after() {
+        let retry_after = headers(&[("retry-after", "12".into())]);
+        for status in [StatusCode::FORBIDDEN, StatusCode::TOO_MANY_REQUESTS] {
+            assert_eq!(
+                retry_delay(status, &retry_after, ""),
+                Some(Duration::from_secs(12))
+            );
+        }
+    }
+
+    #[test]
+    fn backs_off_from_unannounced_secondary_limits() {
+        let body = r#"{"message": "You have exceeded a secondary rate limit."}"#;
+        assert_eq!(
+            retry_delay(StatusCode::FORBIDDEN, &HeaderMap::new(), body),
+            Some(SECONDARY_LIMIT_BACKOFF)
+        );
+        assert_eq!(
+            retry_delay(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new(), ""),
+            Some(SECONDARY_LIMIT_BACKOFF)
+        );
+    }
+
+    #[test]
+    fn ignores_other_errors() {
+        let quota_left = headers(&[
+            ("x-ratelimit-limit", "5000".into()),
+            ("x-ratelimit-remaining", "4999".into()),
+            ("x-ratelimit-reset", "0".into()),
+        ]);
+        let body = r#"{"message": "Must have admin rights to Repository."}"#;
+        assert_eq!(retry_delay(StatusCode::FORBIDDEN, &quota_left, body), None);
+        assert_eq!(retry_delay(StatusCode::NOT_FOUND, &exhausted(30), ""), None);
+        assert_eq!(
+            retry_delay(StatusCode::OK, &headers(&[("retry-after", "5".into())]), ""),
+            None
+        );
+    }
+}
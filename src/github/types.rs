@@ -5,6 +5,7 @@ pub struct Repository {
     pub id: u64,
     pub name: String,
     pub full_name: String,
+    pub owner: String,
     pub description: Option<String>,
     pub private: bool,
     pub fork: bool,
@@ -14,6 +15,105 @@ pub struct Repository {
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
     pub html_url: String,
+    #[serde(default)]
+    pub forks_count: u32,
+    #[serde(default)]
+    pub watchers_count: u32,
+    #[serde(default)]
+    pub open_issues_count: u32,
+    #[serde(default)]
+    pub size_kb: u32,
+    #[serde(default)]
+    pub default_branch: Option<String>,
+    #[serde(default)]
+    pub pushed_at: Option<String>,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub is_template: bool,
+}
+
+#[cfg(test)]
+impl Repository {
+    /// A public, unstarred, non-fork repo named `owner/name`.
+    pub fn fixture(full_name: &str) -> Self {
+        let (owner, name) = full_name.split_once('/').unwrap();
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "name": name,
+            "full_name": full_name,
+            "owner": owner,
+            "private": false,
+            "fork": false,
+            "archived": false,
+            "stargazers_count": 0,
+            "html_url": format!("https://github.com/{}", full_name),
+        }))
+        .unwrap()
+    }
+}
+
+/// Extra details the repo listing doesn't include, fetched on demand for the
+/// preview pane and the risk checks on staged deletions. The README is
+/// fetched separately, only for the preview pane.
+#[derive(Debug, Clone)]
+pub struct RepoDetails {
+    pub parent: Option<String>,
+    /// Accounts watching the repo, including the owner.
+    pub subscribers: u32,
+    pub open_pulls: usize,
+}
+
+/// What a token is allowed to do, as far as GitHub says up front.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenScopes {
+    /// A classic personal access token or OAuth token with these scopes.
+    Classic(Vec<String>),
+    /// A fine-grained token, whose permissions are per repository and only
+    /// show up when a request is refused.
+    FineGrained,
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenInfo {
+    pub login: String,
+    pub scopes: TokenScopes,
+}
+
+impl TokenInfo {
+    /// Why repositories can't be deleted with this token, if that is already
+    /// known.
+    pub fn deletion_blocker(&self) -> Option<&'static str> {
+        match &self.scopes {
+            TokenScopes::Classic(scopes) if !scopes.iter().any(|s| s == "delete_repo") => {
+                Some("token lacks the delete_repo scope")
+            }
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.scopes {
+            TokenScopes::Classic(scopes) if scopes.is_empty() => {
+                "classic token with no scopes".to_string()
+            }
+            TokenScopes::Classic(scopes) => format!("classic token ({})", scopes.join(", ")),
+            TokenScopes::FineGrained => {
+                "fine-grained token (needs Administration: write on each repo)".to_string()
+            }
+        }
+    }
+}
+
+/// Parse a timestamp stored on a `Repository`. Accepts RFC 3339 as well as the
+/// `2024-01-02 03:04:05 UTC` form produced by chrono's `Display`.
+pub fn parse_timestamp(s: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.to_utc())
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S UTC").map(|dt| dt.and_utc())
+        })
+        .ok()
 }
 
 impl From<octocrab::models::Repository> for Repository {
@@ -22,6 +122,7 @@ impl From<octocrab::models::Repository> for Repository {
             id: repo.id.0,
             name: repo.name,
             full_name: repo.full_name.unwrap_or_default(),
+            owner: repo.owner.map(|o| o.login).unwrap_or_default(),
             description: repo.description,
             private: repo.private.unwrap_or(false),
             fork: repo.fork.unwrap_or(false),
@@ -31,6 +132,14 @@ impl From<octocrab::models::Repository> for Repository {
             created_at: repo.created_at.map(|d| d.to_string()),
             updated_at: repo.updated_at.map(|d| d.to_string()),
             html_url: repo.html_url.map(|u| u.to_string()).unwrap_or_default(),
+            forks_count: repo.forks_count.unwrap_or(0),
+            watchers_count: repo.watchers_count.unwrap_or(0),
+            open_issues_count: repo.open_issues_count.unwrap_or(0),
+            size_kb: repo.size.unwrap_or(0),
+            default_branch: repo.default_branch,
+            pushed_at: repo.pushed_at.map(|d| d.to_string()),
+            topics: repo.topics.unwrap_or_default(),
+            is_template: repo.is_template.unwrap_or(false),
         }
     }
 }
@@ -0,0 +1,377 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use clap::Args;
+use color_eyre::eyre::{bail, Result};
+
+use crate::{
+    audit::{AuditEntry, AuditLog},
+    backup::Backup,
+    executor::{self, Job, JobStatus},
+    filter::{self, RepoFilter},
+    fuzzy::FuzzyMatcher,
+    github::{cache::PageCache, client::RepoListing, types::Repository, GitHubClient},
+    plan::{self, PlanEntry},
+    query::Query,
+    safety::SafetyConfig,
+    state::{RepoAction, SortMode},
+};
+
+/// Some, but not all, of the requested actions failed or were cancelled.
+const EXIT_PARTIAL_FAILURE: u8 = 2;
+/// Every requested action failed or was cancelled.
+const EXIT_ALL_FAILED: u8 = 3;
+
+#[derive(Args, Debug)]
+pub struct FilterArgs {
+    /// Search query: filter tokens like `is:fork stars:<5`, with the rest
+    /// fuzzy matched against owner/name
+    #[arg(long, short, default_value = "", hide_default_value = true)]
+    query: String,
+
+    /// Only forked repositories
+    #[arg(long)]
+    forks: bool,
+
+    /// Only private repositories
+    #[arg(long, conflicts_with = "public")]
+    private: bool,
+
+    /// Only public repositories
+    #[arg(long)]
+    public: bool,
+
+    /// Only archived repositories
+    #[arg(long)]
+    archived: bool,
+
+    /// Only empty repositories
+    #[arg(long)]
+    empty: bool,
+
+    /// Only repositories not updated within this long (e.g. 6m, 2y)
+    #[arg(long, value_name = "AGE", value_parser = filter::parse_stale)]
+    stale: Option<chrono::Duration>,
+
+    /// Only repositories owned by this user or organization
+    #[arg(long)]
+    owner: Option<String>,
+
+    /// Sort order
+    #[arg(long, value_enum, default_value_t = SortMode::LastUpdated)]
+    sort: SortMode,
+}
+
+impl FilterArgs {
+    fn to_filter(&self) -> RepoFilter {
+        RepoFilter {
+            query: self.query.clone(),
+            private_only: self.private,
+            public_only: self.public,
+            forks_only: self.forks,
+            archived_only: self.archived,
+            empty_only: self.empty,
+            stale: self.stale,
+            owner: self.owner.clone(),
+            sort: self.sort,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct ListArgs {
+    #[command(flatten)]
+    filter: FilterArgs,
+
+    /// Print repositories as JSON (usable as a plan file for `delete --plan`)
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct DeleteArgs {
+    /// Plan file of repositories and actions, e.g. from `ghune list --json`
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["query", "forks", "private", "public", "archived", "empty", "stale", "owner"])]
+    plan: Option<PathBuf>,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+
+    /// Action applied to repositories selected by a query
+    #[arg(long, value_enum, default_value_t = RepoAction::Delete, conflicts_with = "plan")]
+    action: RepoAction,
+
+    /// Skip the confirmation prompt
+    #[arg(long, short)]
+    yes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Print raw audit log entries as JSON lines
+    #[arg(long)]
+    json: bool,
+
+    /// Only show the most recent N entries
+    #[arg(long, short = 'n', value_name = "N")]
+    limit: Option<usize>,
+}
+
+pub async fn list(client: &GitHubClient, args: ListArgs) -> Result<ExitCode> {
+    let repos = select_repos(client, &args.filter).await?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&repos)?);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    for repo in &repos {
+        let mut flags = Vec::new();
+        if repo.private {
+            flags.push("private");
+        }
+        if repo.fork {
+            flags.push("fork");
+        }
+        if repo.archived {
+            flags.push("archived");
+        }
+        println!(
+            "{}\t{}\t★{}\t{}",
+            repo.full_name,
+            flags.join(","),
+            repo.stargazers_count,
+            repo.updated_at.as_deref().unwrap_or("")
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// How `delete` carries out the changes it selects.
+pub struct DeleteOptions<'a> {
+    pub dry_run: bool,
+    pub concurrency: usize,
+    /// Why the token can't delete repos, if it can't.
+    pub deletion_blocker: Option<&'a str>,
+}
+
+pub async fn delete(
+    client: &GitHubClient,
+    backup: Option<&Backup>,
+    audit: &AuditLog,
+    safety: &SafetyConfig,
+    args: DeleteArgs,
+    options: DeleteOptions<'_>,
+) -> Result<ExitCode> {
+    let DeleteOptions {
+        dry_run,
+        concurrency,
+        deletion_blocker,
+    } = options;
+    let mut entries = match &args.plan {
+        Some(path) => plan::load(path)?,
+        None => select_repos(client, &args.filter)
+            .await?
+            .into_iter()
+            .map(|repo| PlanEntry {
+                repo,
+                action: args.action,
+            })
+            .collect(),
+    };
+
+    entries.retain(|entry| {
+        let protected = safety.is_protected(&entry.repo.full_name);
+        if protected {
+            eprintln!("skipping protected repo {}", entry.repo.full_name);
+        }
+        !protected
+    });
+    // A token that can't delete fails requested deletions up front, like
+    // any other failure.
+    let (blocked, entries): (Vec<PlanEntry>, Vec<PlanEntry>) = entries
+        .into_iter()
+        .partition(|entry| deletion_blocker.is_some() && entry.action == RepoAction::Delete);
+
+    if entries.is_empty() && blocked.is_empty() {
+        eprintln!("No repositories matched.");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    for entry in entries.iter().chain(&blocked) {
+        let risks: Vec<String> = safety
+            .risks(&entry.repo, None)
+            .iter()
+            .map(|risk| risk.label())
+            .collect();
+        if risks.is_empty() {
+            eprintln!("  {:<9} {}", entry.action.label(), entry.repo.full_name);
+        } else {
+            eprintln!(
+                "  {:<9} {}  ! {}",
+                entry.action.label(),
+                entry.repo.full_name,
+                risks.join(", ")
+            );
+        }
+    }
+
+    if !args.yes && !dry_run {
+        confirm(entries.len())?;
+    }
+
+    let user = client.current_user().await.ok();
+    let record = |entry: &AuditEntry| {
+        if let Err(e) = audit.record(entry) {
+            eprintln!("warning: could not write audit log: {:#}", e);
+        }
+    };
+    let blocker = deletion_blocker.unwrap_or_default();
+    for entry in &blocked {
+        let name = &entry.repo.full_name;
+        eprintln!("failed to delete {}: {}", name, blocker);
+        record(&AuditEntry::new(
+            user.as_deref(),
+            name,
+            Some(entry.repo.id),
+            RepoAction::Delete,
+            dry_run,
+            Some(blocker.to_string()),
+        ));
+    }
+
+    let jobs: Vec<Job> = entries
+        .into_iter()
+        .map(|entry| Job {
+            repo: entry.repo,
+            action: entry.action,
+        })
+        .collect();
+    let failed = AtomicUsize::new(blocked.len());
+    let cancel = AtomicBool::new(false);
+
+    let report = |index: usize, status: JobStatus| {
+        let job = &jobs[index];
+        let name = &job.repo.full_name;
+        let error = match status {
+            JobStatus::Succeeded if dry_run => {
+                println!("[DRY RUN] would {} {}", job.action.label(), name);
+                None
+            }
+            JobStatus::Succeeded => {
+                println!("{} {}", job.action.past_tense(), name);
+                None
+            }
+            JobStatus::Failed(e) => {
+                failed.fetch_add(1, Ordering::Relaxed);
+                eprintln!("failed to {} {}: {}", job.action.label(), name, e);
+                Some(e)
+            }
+            JobStatus::Cancelled => {
+                failed.fetch_add(1, Ordering::Relaxed);
+                eprintln!("cancelled {}", name);
+                return;
+            }
+            JobStatus::Queued | JobStatus::BackingUp | JobStatus::Running => return,
+        };
+
+        record(&AuditEntry::new(
+            user.as_deref(),
+            name,
+            Some(job.repo.id),
+            job.action,
+            dry_run,
+            error,
+        ));
+    };
+
+    let run = executor::run(client, backup, &jobs, concurrency, dry_run, &cancel, report);
+    tokio::pin!(run);
+    tokio::select! {
+        _ = &mut run => {}
+        _ = tokio::signal::ctrl_c() => {
+            eprintln!("Cancelling: waiting for in-flight requests...");
+            cancel.store(true, Ordering::Relaxed);
+            run.await;
+        }
+    }
+
+    let failed = failed.load(Ordering::Relaxed);
+    Ok(if failed == 0 {
+        ExitCode::SUCCESS
+    } else if failed == jobs.len() + blocked.len() {
+        ExitCode::from(EXIT_ALL_FAILED)
+    } else {
+        ExitCode::from(EXIT_PARTIAL_FAILURE)
+    })
+}
+
+pub fn history(audit: &AuditLog, args: HistoryArgs) -> Result<ExitCode> {
+    let entries = audit.read_all()?;
+    let skip = args
+        .limit
+        .map_or(0, |limit| entries.len().saturating_sub(limit));
+
+    for entry in entries.iter().skip(skip) {
+        if args.json {
+            println!("{}", serde_json::to_string(entry)?);
+            continue;
+        }
+        let outcome = match (&entry.error, entry.dry_run) {
+            (Some(err), _) => format!("failed: {}", err),
+            (None, true) => "ok (dry run)".to_string(),
+            (None, false) => "ok".to_string(),
+        };
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            entry.timestamp,
+            entry.user.as_deref().unwrap_or("-"),
+            entry.action.label(),
+            entry.repo,
+            outcome
+        );
+    }
+
+    if entries.is_empty() && !args.json {
+        eprintln!("No history yet ({})", audit.path().display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn select_repos(client: &GitHubClient, args: &FilterArgs) -> Result<Vec<Repository>> {
+    if let Some(err) = Query::parse(&args.query).errors.first() {
+        bail!("Invalid query: {}", err);
+    }
+
+    let RepoListing {
+        repos, org_errors, ..
+    } = client
+        .list_all_repos(&PageCache::default(), &mut |_| {})
+        .await?;
+    for err in &org_errors {
+        eprintln!("warning: failed to list organization repos: {}", err);
+    }
+
+    let mut fuzzy = FuzzyMatcher::new();
+    let indices = args.to_filter().apply(&mut fuzzy, &repos);
+    Ok(indices.into_iter().map(|idx| repos[idx].clone()).collect())
+}
+
+/// Same confirmation as the TUI dialog: type the number of repos affected.
+fn confirm(count: usize) -> Result<()> {
+    if !io::stdin().is_terminal() {
+        bail!("Refusing to modify {} repos without --yes", count);
+    }
+
+    eprint!("Type {} to apply these changes: ", count);
+    io::stderr().flush()?;
+
+    let mut input = String::new();
+    io::stdin().lock().read_line(&mut input)?;
+    if input.trim() != count.to_string() {
+        bail!("Aborted");
+    }
+    Ok(())
+}
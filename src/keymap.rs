@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use crate::state::AppMode;
+
+/// Everything a key can be bound to. The names are what `[keys.*]` sections
+/// of the config file use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    Quit,
+    Help,
+    Down,
+    Up,
+    Top,
+    Bottom,
+    Stage,
+    StageArchive,
+    StageVisible,
+    UnstageVisible,
+    InvertVisible,
+    VisualRange,
+    FocusStaging,
+    Refresh,
+    TogglePrivate,
+    ToggleForks,
+    CycleOwner,
+    OpenHistory,
+    CycleSort,
+    ToggleDetails,
+    ClearQuery,
+    DeleteChar,
+    Undo,
+    Redo,
+    Unstage,
+    UnstageAll,
+    CycleAction,
+    WritePlan,
+    Apply,
+    Back,
+    Acknowledge,
+    CancelJobs,
+}
+
+impl KeyAction {
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyAction::Quit => "quit",
+            KeyAction::Help => "help",
+            KeyAction::Down => "down",
+            KeyAction::Up => "up",
+            KeyAction::Top => "top",
+            KeyAction::Bottom => "bottom",
+            KeyAction::Stage => "stage",
+            KeyAction::StageArchive => "stage_archive",
+            KeyAction::StageVisible => "stage_visible",
+            KeyAction::UnstageVisible => "unstage_visible",
+            KeyAction::InvertVisible => "invert_visible",
+            KeyAction::VisualRange => "visual_range",
+            KeyAction::FocusStaging => "focus_staging",
+            KeyAction::Refresh => "refresh",
+            KeyAction::TogglePrivate => "toggle_private",
+            KeyAction::ToggleForks => "toggle_forks",
+            KeyAction::CycleOwner => "cycle_owner",
+            KeyAction::OpenHistory => "open_history",
+            KeyAction::CycleSort => "cycle_sort",
+            KeyAction::ToggleDetails => "toggle_details",
+            KeyAction::ClearQuery => "clear_query",
+            KeyAction::DeleteChar => "delete_char",
+            KeyAction::Undo => "undo",
+            KeyAction::Redo => "redo",
+            KeyAction::Unstage => "unstage",
+            KeyAction::UnstageAll => "unstage_all",
+            KeyAction::CycleAction => "cycle_action",
+            KeyAction::WritePlan => "write_plan",
+            KeyAction::Apply => "apply",
+            KeyAction::Back => "back",
+            KeyAction::Acknowledge => "acknowledge",
+            KeyAction::CancelJobs => "cancel_jobs",
+        }
+    }
+
+    /// What the action does, for the help overlay.
+    pub fn description(self) -> &'static str {
+        match self {
+            KeyAction::Quit => "Quit",
+            KeyAction::Help => "Toggle this help",
+            KeyAction::Down => "Move down",
+            KeyAction::Up => "Move up",
+            KeyAction::Top => "Jump to the first item",
+            KeyAction::Bottom => "Jump to the last item",
+            KeyAction::Stage => "Stage or unstage for deletion",
+            KeyAction::StageArchive => "Stage or unstage for (un)archiving",
+            KeyAction::StageVisible => "Stage every repo matching the filter",
+            KeyAction::UnstageVisible => "Unstage every repo matching the filter",
+            KeyAction::InvertVisible => "Invert staging of repos matching the filter",
+            KeyAction::VisualRange => "Start or cancel a range selection",
+            KeyAction::FocusStaging => "Switch to the staging pane",
+            KeyAction::Refresh => "Reload repositories",
+            KeyAction::TogglePrivate => "Toggle private-only filter",
+            KeyAction::ToggleForks => "Toggle forks-only filter",
+            KeyAction::CycleOwner => "Cycle owner filter",
+            KeyAction::OpenHistory => "Browse deletion history",
+            KeyAction::CycleSort => "Cycle sort order",
+            KeyAction::ToggleDetails => "Toggle the detail pane",
+            KeyAction::ClearQuery => "Clear the search",
+            KeyAction::DeleteChar => "Delete the last character",
+            KeyAction::Undo => "Undo the last staging change",
+            KeyAction::Redo => "Redo the last undone staging change",
+            KeyAction::Unstage => "Unstage the selected repo",
+            KeyAction::UnstageAll => "Unstage every repo",
+            KeyAction::CycleAction => "Switch between delete and archive",
+            KeyAction::WritePlan => "Write the staged set to a plan file",
+            KeyAction::Apply => "Apply the staged changes",
+            KeyAction::Back => "Go back",
+            KeyAction::Acknowledge => "Acknowledge the risky repo shown",
+            KeyAction::CancelJobs => "Cancel changes not yet started",
+        }
+    }
+}
+
+type Defaults = &'static [(KeyAction, &'static [&'static str])];
+
+const SEARCH_DEFAULTS: Defaults = &[
+    (KeyAction::Quit, &["ctrl-c", "q"]),
+    (KeyAction::Help, &["?"]),
+    (KeyAction::Down, &["ctrl-j", "down"]),
+    (KeyAction::Up, &["ctrl-k", "up"]),
+    (KeyAction::Top, &["home"]),
+    (KeyAction::Bottom, &["end"]),
+    (KeyAction::Stage, &["enter", "space"]),
+    (KeyAction::StageArchive, &["ctrl-a"]),
+    (KeyAction::StageVisible, &["alt-a"]),
+    (KeyAction::UnstageVisible, &["alt-u"]),
+    (KeyAction::InvertVisible, &["alt-i"]),
+    (KeyAction::VisualRange, &["ctrl-v"]),
+    (KeyAction::FocusStaging, &["tab"]),
+    (KeyAction::Undo, &["ctrl-z"]),
+    (KeyAction::Redo, &["ctrl-y"]),
+    (KeyAction::Refresh, &["ctrl-r"]),
+    (KeyAction::TogglePrivate, &["ctrl-p"]),
+    (KeyAction::ToggleForks, &["ctrl-f"]),
+    (KeyAction::CycleOwner, &["ctrl-o"]),
+    (KeyAction::OpenHistory, &["ctrl-l"]),
+    (KeyAction::CycleSort, &["ctrl-s"]),
+    (KeyAction::ToggleDetails, &["ctrl-d"]),
+    (KeyAction::ClearQuery, &["esc"]),
+    (KeyAction::DeleteChar, &["backspace"]),
+];
+
+const STAGING_DEFAULTS: Defaults = &[
+    (KeyAction::Quit, &["ctrl-c", "q"]),
+    (KeyAction::Help, &["?"]),
+    (KeyAction::Down, &["ctrl-j", "j", "down"]),
+    (KeyAction::Up, &["ctrl-k", "k", "up"]),
+    (KeyAction::Top, &["g", "home"]),
+    (KeyAction::Bottom, &["G", "end"]),
+    (KeyAction::Unstage, &["backspace", "delete", "space"]),
+    (KeyAction::UnstageAll, &["X"]),
+    (KeyAction::Undo, &["u", "ctrl-z"]),
+    (KeyAction::Redo, &["ctrl-r", "ctrl-y"]),
+    (KeyAction::CycleAction, &["a"]),
+    (KeyAction::WritePlan, &["w"]),
+    (KeyAction::Apply, &["enter"]),
+    (KeyAction::Back, &["tab", "esc"]),
+];
+
+const CONFIRM_DEFAULTS: Defaults = &[
+    (KeyAction::Quit, &["ctrl-c"]),
+    (KeyAction::Help, &["?"]),
+    (KeyAction::Acknowledge, &["y"]),
+    (KeyAction::Apply, &["enter"]),
+    (KeyAction::DeleteChar, &["backspace"]),
+    (KeyAction::Back, &["esc"]),
+];
+
+const DELETING_DEFAULTS: Defaults = &[
+    (KeyAction::Help, &["?"]),
+    (KeyAction::CancelJobs, &["esc", "ctrl-c"]),
+    (KeyAction::Back, &["enter", "q"]),
+];
+
+const HISTORY_DEFAULTS: Defaults = &[
+    (KeyAction::Quit, &["ctrl-c"]),
+    (KeyAction::Help, &["?"]),
+    (KeyAction::Down, &["ctrl-j", "j", "down"]),
+    (KeyAction::Up, &["ctrl-k", "k", "up"]),
+    (KeyAction::Top, &["g", "home"]),
+    (KeyAction::Bottom, &["G", "end"]),
+    (KeyAction::Back, &["ctrl-l", "q", "esc"]),
+];
+
+/// Every mode with its `[keys.*]` section name and default bindings.
+const MODES: &[(AppMode, &str, Defaults)] = &[
+    (AppMode::Search, "search", SEARCH_DEFAULTS),
+    (AppMode::Staging, "staging", STAGING_DEFAULTS),
+    (AppMode::ConfirmDeletion, "confirm", CONFIRM_DEFAULTS),
+    (AppMode::Deleting, "deleting", DELETING_DEFAULTS),
+    (AppMode::History, "history", HISTORY_DEFAULTS),
+];
+
+/// A key plus modifiers, as written in the config file (`ctrl-j`, `enter`,
+/// `q`). Shift is folded into the character for printable keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // A trailing "-" is the minus key itself, not a separator.
+        while let Some((modifier, tail)) = rest.split_once('-').filter(|(_, t)| !t.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "c" => KeyModifiers::CONTROL,
+                "alt" | "a" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{}' in '{}'", modifier, s)),
+            };
+            rest = tail;
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                _ => {
+                    let mut chars = rest.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                            KeyCode::Char(c.to_ascii_uppercase())
+                        }
+                        (Some(c), None) => KeyCode::Char(c),
+                        _ => return Err(format!("unknown key '{}'", s)),
+                    }
+                }
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "C-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "M-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "S-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::BackTab => write!(f, "S-Tab"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Bindings for one mode, in the order they're listed in hints.
+#[derive(Debug, Clone, Default)]
+struct Bindings {
+    actions: Vec<(KeyAction, Vec<KeyChord>)>,
+    by_chord: HashMap<KeyChord, KeyAction>,
+}
+
+impl Bindings {
+    fn new(
+        section: &str,
+        defaults: Defaults,
+        overrides: &HashMap<KeyAction, Vec<String>>,
+    ) -> Result<Self, String> {
+        for action in overrides.keys() {
+            if !defaults.iter().any(|(a, _)| a == action) {
+                return Err(format!(
+                    "[keys.{}]: '{}' isn't available in this mode",
+                    section,
+                    action.name()
+                ));
+            }
+        }
+
+        let mut bindings = Bindings::default();
+        for &(action, keys) in defaults {
+            let chords = match overrides.get(&action) {
+                Some(keys) => keys
+                    .iter()
+                    .map(|k| KeyChord::parse(k))
+                    .collect::<Result<Vec<_>, _>>(),
+                None => keys.iter().map(|k| KeyChord::parse(k)).collect(),
+            }
+            .map_err(|e| format!("[keys.{}] {}: {}", section, action.name(), e))?;
+
+            for &chord in &chords {
+                if let Some(other) = bindings.by_chord.insert(chord, action) {
+                    return Err(format!(
+                        "[keys.{}]: {} is bound to both '{}' and '{}'",
+                        section,
+                        chord,
+                        other.name(),
+                        action.name()
+                    ));
+                }
+            }
+            bindings.actions.push((action, chords));
+        }
+        Ok(bindings)
+    }
+}
+
+/// The `[keys.<mode>]` config sections: action name to the list of keys
+/// that replace its default bindings in that mode.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysConfig {
+    pub search: HashMap<KeyAction, Vec<String>>,
+    pub staging: HashMap<KeyAction, Vec<String>>,
+    pub confirm: HashMap<KeyAction, Vec<String>>,
+    pub deleting: HashMap<KeyAction, Vec<String>>,
+    pub history: HashMap<KeyAction, Vec<String>>,
+}
+
+impl KeysConfig {
+    fn section(&self, name: &str) -> &HashMap<KeyAction, Vec<String>> {
+        match name {
+            "search" => &self.search,
+            "staging" => &self.staging,
+            "confirm" => &self.confirm,
+            "deleting" => &self.deleting,
+            _ => &self.history,
+        }
+    }
+}
+
+/// Key chord to action lookup for every mode.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    modes: HashMap<AppMode, Bindings>,
+}
+
+impl Keymap {
+    pub fn new(config: &KeysConfig) -> Result<Self, String> {
+        let mut modes = HashMap::new();
+        for &(mode, section, defaults) in MODES {
+            modes.insert(
+                mode,
+                Bindings::new(section, defaults, config.section(section))?,
+            );
+        }
+        Ok(Self { modes })
+    }
+
+    pub fn action(&self, mode: AppMode, key: KeyEvent) -> Option<KeyAction> {
+        self.modes
+            .get(&mode)?
+            .by_chord
+            .get(&KeyChord::from(key))
+            .copied()
+    }
+
+    /// The keys bound to `action` in `mode`, for hints.
+    pub fn keys(&self, mode: AppMode, action: KeyAction) -> &[KeyChord] {
+        self.bindings(mode)
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or(&[], |(_, chords)| chords.as_slice())
+    }
+
+    /// The first key bound to `action` in `mode` as shown in hints, or
+    /// `None` if it has been unbound.
+    pub fn hint(&self, mode: AppMode, action: KeyAction) -> Option<String> {
+        self.keys(mode, action).first().map(|k| k.to_string())
+    }
+
+    /// Every action available in `mode` with its keys, in display order.
+    pub fn bindings(&self, mode: AppMode) -> &[(KeyAction, Vec<KeyChord>)] {
+        self.modes.get(&mode).map_or(&[], |b| b.actions.as_slice())
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&KeysConfig::default()).expect("default keymap is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord::new(code, modifiers)
+    }
+
+    #[test]
+    fn parses_modifier_aliases() {
+        let ctrl_j = chord(KeyCode::Char('j'), KeyModifiers::CONTROL);
+        for s in ["ctrl-j", "c-j", "C-j", "CTRL-j"] {
+            assert_eq!(KeyChord::parse(s), Ok(ctrl_j), "{}", s);
+        }
+        let alt_a = chord(KeyCode::Char('a'), KeyModifiers::ALT);
+        for s in ["alt-a", "a-a", "m-a", "M-a"] {
+            assert_eq!(KeyChord::parse(s), Ok(alt_a), "{}", s);
+        }
+        assert_eq!(
+            KeyChord::parse("ctrl-alt-enter"),
+            Ok(chord(
+                KeyCode::Enter,
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            ))
+        );
+    }
+
+    #[test]
+    fn folds_shift_into_characters() {
+        let upper_g = chord(KeyCode::Char('G'), KeyModifiers::NONE);
+        assert_eq!(KeyChord::parse("G"), Ok(upper_g));
+        assert_eq!(KeyChord::parse("shift-g"), Ok(upper_g));
+        assert_eq!(KeyChord::parse("s-g"), Ok(upper_g));
+        assert_eq!(
+            KeyChord::parse("shift-tab"),
+            Ok(chord(KeyCode::Tab, KeyModifiers::SHIFT))
+        );
+    }
+
+    #[test]
+    fn parses_named_keys() {
+        assert_eq!(
+            KeyChord::parse("-"),
+            Ok(chord(KeyCode::Char('-'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            KeyChord::parse("ctrl--"),
+            Ok(chord(KeyCode::Char('-'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            KeyChord::parse("Escape"),
+            Ok(chord(KeyCode::Esc, KeyModifiers::NONE))
+        );
+        assert_eq!(
+            KeyChord::parse("f12"),
+            Ok(chord(KeyCode::F(12), KeyModifiers::NONE))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_modifiers() {
+        assert_eq!(
+            KeyChord::parse("ctrl-foo"),
+            Err("unknown key 'ctrl-foo'".to_string())
+        );
+        assert_eq!(KeyChord::parse("f13"), Err("unknown key 'f13'".to_string()));
+        assert_eq!(KeyChord::parse(""), Err("unknown key ''".to_string()));
+        assert_eq!(
+            KeyChord::parse("hyper-x"),
+            Err("unknown modifier 'hyper' in 'hyper-x'".to_string())
+        );
+    }
+
+    #[test]
+    fn displays_as_shown_in_hints() {
+        for (s, shown) in [
+            ("ctrl-r", "C-r"),
+            ("alt-a", "M-a"),
+            ("space", "Space"),
+            ("enter", "Enter"),
+            ("backtab", "S-Tab"),
+        ] {
+            assert_eq!(KeyChord::parse(s).unwrap().to_string(), shown);
+        }
+    }
+
+    #[test]
+    fn rejects_conflicting_bindings() {
+        let overrides = HashMap::from([(KeyAction::Refresh, vec!["ctrl-p".to_string()])]);
+        let err = Bindings::new("search", SEARCH_DEFAULTS, &overrides).unwrap_err();
+        assert_eq!(
+            err,
+            "[keys.search]: C-p is bound to both 'refresh' and 'toggle_private'"
+        );
+    }
+
+    #[test]
+    fn rejects_actions_outside_their_mode() {
+        let overrides = HashMap::from([(KeyAction::Apply, vec!["a".to_string()])]);
+        let err = Bindings::new("history", HISTORY_DEFAULTS, &overrides).unwrap_err();
+        assert_eq!(err, "[keys.history]: 'apply' isn't available in this mode");
+    }
+
+    #[test]
+    fn overrides_replace_defaults() {
+        let overrides = HashMap::from([(KeyAction::Refresh, vec!["F5".to_string()])]);
+        let bindings = Bindings::new("search", SEARCH_DEFAULTS, &overrides).unwrap();
+        assert_eq!(
+            bindings.by_chord.get(&KeyChord::parse("f5").unwrap()),
+            Some(&KeyAction::Refresh)
+        );
+        assert!(!bindings
+            .by_chord
+            .contains_key(&KeyChord::parse("ctrl-r").unwrap()));
+    }
+
+    #[test]
+    fn default_keymap_has_no_conflicts() {
+        let keymap = Keymap::new(&KeysConfig::default()).unwrap();
+        assert_eq!(
+            keymap.hint(AppMode::Search, KeyAction::ToggleDetails),
+            Some("C-d".to_string())
+        );
+    }
+}
@@ -1,54 +1,428 @@
 mod app;
+mod audit;
 mod auth;
+mod backup;
+mod config;
+mod credentials;
+mod executor;
+mod filter;
 mod fuzzy;
 mod github;
+mod headless;
+mod keymap;
+mod plan;
+mod query;
+mod safety;
 mod state;
 mod tui;
 
-use clap::Parser;
-use color_eyre::eyre::Result;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
 
-use app::App;
-use auth::TokenManager;
+use clap::{Parser, Subcommand};
+use color_eyre::eyre::{bail, eyre, Result};
+
+use app::{App, AppOptions};
+use audit::AuditLog;
+use auth::{TokenManager, TokenSource};
+use backup::{Backup, BackupOptions};
+use config::Config;
+use credentials::TokenStore;
+use filter::RepoFilter;
+use github::{
+    device::{DeviceFlow, LOGIN_SCOPES},
+    types::TokenInfo,
+    GitHubClient, Host,
+};
+use keymap::Keymap;
+use state::SortMode;
+
+/// Repositories changed in parallel when neither `--concurrency` nor the
+/// config file says otherwise.
+const DEFAULT_CONCURRENCY: usize = 4;
 
 #[derive(Parser)]
 #[command(name = "ghune")]
 #[command(about = "Interactive GitHub repository deletion tool")]
 #[command(version)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Run without actually deleting repositories
-    #[arg(long)]
+    #[arg(long, global = true)]
     dry_run: bool,
 
+    /// Actually apply changes even if the config file enables dry-run
+    #[arg(long, global = true, conflicts_with = "dry_run")]
+    no_dry_run: bool,
+
     /// Clear stored GitHub token
     #[arg(long)]
     logout: bool,
 
+    /// Use the token stored under this profile instead of the default one
+    #[arg(long, global = true, env = "GHUNE_PROFILE")]
+    profile: Option<String>,
+
+    /// Read the GitHub token from standard input instead of the environment
+    /// or the token store; nothing is saved
+    #[arg(long, global = true)]
+    token_stdin: bool,
+
+    /// GitHub Enterprise Server host (or URL) to use instead of github.com
+    #[arg(long, global = true, env = "GHUNE_HOST")]
+    host: Option<String>,
+
+    /// Where to keep tokens: the OS keyring when available, or a file
+    #[arg(long, global = true, value_enum, default_value_t)]
+    token_store: TokenStore,
+
     /// Show only forked repositories
     #[arg(long)]
     forks_only: bool,
 
     /// Show only private repositories
-    #[arg(long)]
+    #[arg(long, conflicts_with = "public_only")]
     private_only: bool,
+
+    /// Show only public repositories
+    #[arg(long)]
+    public_only: bool,
+
+    /// Show only archived repositories
+    #[arg(long)]
+    archived_only: bool,
+
+    /// Show only empty repositories
+    #[arg(long)]
+    empty_only: bool,
+
+    /// Show only repositories not updated within this long (e.g. 6m, 2y)
+    #[arg(long, value_name = "AGE", value_parser = filter::parse_stale)]
+    stale: Option<chrono::Duration>,
+
+    /// Initial sort order
+    #[arg(long, value_enum)]
+    sort: Option<SortMode>,
+
+    /// Initial search query, e.g. "is:fork stars:<5"
+    #[arg(long, short)]
+    query: Option<String>,
+
+    /// Number of repositories to delete or archive in parallel [default: 4]
+    #[arg(long, global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    concurrency: Option<u16>,
+
+    /// Stage the repos in this JSON or TOML plan file; `w` in the staging
+    /// pane writes the staged set back to it
+    #[arg(long, value_name = "FILE")]
+    plan: Option<PathBuf>,
+
+    /// Back up repos to this directory as verified git bundles before deleting
+    #[arg(long, value_name = "DIR", global = true)]
+    backup_dir: Option<PathBuf>,
+
+    /// Also save issues and pull requests as JSON alongside each backup
+    #[arg(long, requires = "backup_dir", global = true)]
+    backup_metadata: bool,
+}
+
+impl Cli {
+    /// The TUI's starting filter: config defaults overridden by flags.
+    fn startup_filter(&self, defaults: RepoFilter) -> RepoFilter {
+        let mut filter = defaults;
+        if self.private_only {
+            filter.private_only = true;
+            filter.public_only = false;
+        }
+        if self.public_only {
+            filter.public_only = true;
+            filter.private_only = false;
+        }
+        filter.forks_only |= self.forks_only;
+        filter.archived_only |= self.archived_only;
+        filter.empty_only |= self.empty_only;
+        if let Some(stale) = self.stale {
+            filter.stale = Some(stale);
+        }
+        if let Some(sort) = self.sort {
+            filter.sort = sort;
+        }
+        if let Some(query) = &self.query {
+            filter.query = query.clone();
+        }
+        filter
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print repositories matching a query without starting the TUI
+    List(headless::ListArgs),
+    /// Delete or archive repositories from a query or plan file without the TUI
+    Delete(headless::DeleteArgs),
+    /// Show the audit log of past deletions and archives
+    History(headless::HistoryArgs),
+    /// Log in through the browser (or with a pasted token) and store the
+    /// token for the profile
+    Login(LoginArgs),
+    /// List profiles with a stored token
+    Profiles,
+    /// Inspect authentication
+    #[command(subcommand)]
+    Auth(AuthCommand),
+}
+
+#[derive(clap::Args)]
+struct LoginArgs {
+    /// Paste a personal access token instead of authorizing in the browser
+    #[arg(long)]
+    with_token: bool,
+
+    /// Client ID of the OAuth app to authorize (overrides `login.client_id`
+    /// in the config file)
+    #[arg(long, env = "GHUNE_OAUTH_CLIENT_ID")]
+    client_id: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum AuthCommand {
+    /// Show which token source is in use and who it belongs to, without
+    /// printing the token
+    Status,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     color_eyre::install()?;
 
     let cli = Cli::parse();
 
-    let token_manager = TokenManager::new()?;
+    let config = Config::load()?;
+    let host = match cli.host.as_deref().or(config.github.host.as_deref()) {
+        Some(host) => Host::parse(host)?,
+        None => Host::default(),
+    };
+    let token_manager = TokenManager::new(&host, cli.profile.as_deref(), cli.token_store)?;
 
     if cli.logout {
         token_manager.clear_token()?;
         println!("GitHub token cleared.");
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    match cli.command {
+        Some(Command::History(args)) => return headless::history(&AuditLog::new()?, args),
+        Some(Command::Login(args)) => {
+            let client_id = args.client_id.or(config.login.client_id.clone());
+            return login(&token_manager, client_id, args.with_token, cli.token_stdin).await;
+        }
+        Some(Command::Profiles) => {
+            for profile in token_manager.list_profiles()? {
+                let marker = if profile == token_manager.profile() {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{} {}", marker, profile);
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Auth(AuthCommand::Status)) => {
+            return auth_status(&token_manager, cli.token_stdin).await;
+        }
+        _ => {}
+    }
+
+    let filter = cli.startup_filter(config.defaults.filter()?);
+    let mut dry_run = cli.dry_run || (config.defaults.dry_run && !cli.no_dry_run);
+    let concurrency = cli
+        .concurrency
+        .map(usize::from)
+        .or(config.defaults.concurrency)
+        .unwrap_or(DEFAULT_CONCURRENCY);
+    let (token, token_info) = authenticate(&token_manager, cli.token_stdin).await?;
+    if let Some(blocker) = token_info.deletion_blocker() {
+        if !dry_run && matches!(cli.command, Some(Command::Delete(_))) {
+            eprintln!("Warning: {}; running in dry-run mode.", blocker);
+        }
+        dry_run = true;
+    }
+
+    let backup = cli.backup_dir.map(|dir| BackupOptions {
+        dir,
+        include_metadata: cli.backup_metadata,
+    });
+
+    match cli.command {
+        Some(Command::List(args)) => {
+            let client = GitHubClient::new(&token, token_manager.host()).await?;
+            headless::list(&client, args).await
+        }
+        Some(Command::Delete(args)) => {
+            let client = GitHubClient::new(&token, token_manager.host()).await?;
+            let backup = backup.map(|options| Backup::new(&token, options));
+            let audit = AuditLog::new()?;
+            headless::delete(
+                &client,
+                backup.as_ref(),
+                &audit,
+                &config.safety,
+                args,
+                headless::DeleteOptions {
+                    dry_run,
+                    concurrency,
+                    deletion_blocker: token_info.deletion_blocker(),
+                },
+            )
+            .await
+        }
+        Some(Command::History(_) | Command::Login(_) | Command::Profiles | Command::Auth(_)) => {
+            unreachable!("handled before authentication")
+        }
+        None => {
+            let options = AppOptions {
+                dry_run,
+                backup,
+                plan_path: cli.plan,
+                concurrency,
+                safety: config.safety,
+                filter,
+                keymap: Keymap::new(&config.keys).map_err(|e| eyre!(e))?,
+                theme: config.theme,
+                profile: token_manager.profile().to_string(),
+                token: token_info,
+                host,
+            };
+            let mut app = App::new(&token, options).await?;
+            app.run().await?;
+            Ok(ExitCode::SUCCESS)
+        }
     }
+}
+
+/// Get a token GitHub accepts, offering to log in again if the stored one
+/// has expired or been revoked. A token typed at the prompt is only stored
+/// once it has been accepted.
+async fn authenticate(token_manager: &TokenManager, stdin: bool) -> Result<(String, TokenInfo)> {
+    let (mut token, mut source) = token_manager.get_or_prompt_token(stdin)?;
+    loop {
+        if let Some(info) = GitHubClient::new(&token, token_manager.host())
+            .await?
+            .token_info()
+            .await?
+        {
+            if source == TokenSource::Prompt {
+                token_manager.store_token(&token)?;
+            }
+            return Ok((token, info));
+        }
 
-    let token = token_manager.get_or_prompt_token()?;
+        // Tokens from the environment, stdin or gh are someone else's to
+        // replace.
+        let interactive = matches!(source, TokenSource::Store(_) | TokenSource::Prompt)
+            && io::stdin().is_terminal();
+        if !interactive {
+            bail!(
+                "GitHub rejected the token from {}; it may have expired or been revoked",
+                source
+            );
+        }
+
+        eprint!(
+            "GitHub rejected the token for profile '{}'; it may have expired or been revoked. Log in again? [Y/n] ",
+            token_manager.profile()
+        );
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if answer.trim().eq_ignore_ascii_case("n") {
+            bail!("Not logged in");
+        }
+        eprintln!();
+        token = token_manager.read_new_token(false)?;
+        source = TokenSource::Prompt;
+    }
+}
 
-    let mut app = App::new(&token, cli.dry_run).await?;
-    app.run().await
+/// Get a new token for the profile, through the device flow when an OAuth
+/// client ID is configured, and store it once GitHub accepts it.
+async fn login(
+    token_manager: &TokenManager,
+    client_id: Option<String>,
+    with_token: bool,
+    stdin: bool,
+) -> Result<ExitCode> {
+    let token = match client_id {
+        Some(client_id) if !with_token && !stdin => {
+            let flow = DeviceFlow::new(token_manager.host().web_url(), &client_id)?;
+            let code = flow.start(LOGIN_SCOPES).await?;
+            eprintln!("First copy your one-time code: {}", code.user_code);
+            eprintln!("Then open {} and enter it.", code.verification_uri);
+            eprintln!("Waiting for authorization...");
+            flow.poll(&code).await?
+        }
+        client_id => {
+            if client_id.is_none() && !with_token && !stdin {
+                eprintln!("No OAuth client ID configured (login.client_id); asking for a personal access token instead.");
+                eprintln!();
+            }
+            token_manager.read_new_token(stdin)?
+        }
+    };
+
+    let Some(info) = GitHubClient::new(&token, token_manager.host())
+        .await?
+        .token_info()
+        .await?
+    else {
+        bail!("GitHub rejected this token; it may have expired or been revoked");
+    };
+    token_manager.store_token(&token)?;
+    println!(
+        "Logged in as {} (profile '{}', token stored in the {}).",
+        info.login,
+        token_manager.profile(),
+        token_manager.store_name()
+    );
+    println!("Token: {}", info.describe());
+    if let Some(blocker) = info.deletion_blocker() {
+        println!("Warning: {}; ghune will only run in dry-run mode.", blocker);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn auth_status(token_manager: &TokenManager, stdin: bool) -> Result<ExitCode> {
+    println!("Host:    {}", token_manager.host());
+    println!("Profile: {}", token_manager.profile());
+    let Some((token, source)) = token_manager.find_token(stdin)? else {
+        println!("Token:   none found (run `ghune login`)");
+        return Ok(ExitCode::FAILURE);
+    };
+    println!("Token:   from {}", source);
+
+    match GitHubClient::new(&token, token_manager.host())
+        .await?
+        .token_info()
+        .await
+    {
+        Ok(Some(info)) => {
+            println!("Account: {}", info.login);
+            println!("Type:    {}", info.describe());
+            if let Some(blocker) = info.deletion_blocker() {
+                println!("Warning: {}; ghune will only run in dry-run mode", blocker);
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Ok(None) => {
+            println!("Account: token rejected (expired or revoked)");
+            Ok(ExitCode::FAILURE)
+        }
+        Err(e) => {
+            println!("Account: could not verify the token: {}", e.root_cause());
+            Ok(ExitCode::FAILURE)
+        }
+    }
 }
@@ -0,0 +1,125 @@
+use std::fs;
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{github::types::Repository, state::RepoAction};
+
+/// One repo in a plan file. Entries are plain repository records (as printed
+/// by `ghune list --json`) with an optional `action`, defaulting to delete.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanEntry {
+    #[serde(flatten)]
+    pub repo: Repository,
+    #[serde(default)]
+    pub action: RepoAction,
+}
+
+/// TOML has no top-level arrays, so TOML plans are a list of `[[repos]]`
+/// tables instead of the bare array used for JSON.
+#[derive(Serialize, Deserialize)]
+struct TomlPlan {
+    repos: Vec<PlanEntry>,
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+}
+
+pub fn load(path: &Path) -> Result<Vec<PlanEntry>> {
+    let contents = fs::read_to_string(path)
+        .wrap_err_with(|| format!("Could not read plan file {}", path.display()))?;
+    let entries: Result<Vec<PlanEntry>> = if is_toml(path) {
+        toml::from_str::<TomlPlan>(&contents)
+            .map(|plan| plan.repos)
+            .map_err(Into::into)
+    } else {
+        serde_json::from_str(&contents).map_err(Into::into)
+    };
+    entries.wrap_err_with(|| format!("Invalid plan file {}", path.display()))
+}
+
+pub fn save(path: &Path, entries: &[PlanEntry]) -> Result<()> {
+    let contents = if is_toml(path) {
+        toml::to_string_pretty(&TomlPlan {
+            repos: entries.to_vec(),
+        })?
+    } else {
+        serde_json::to_string_pretty(entries)? + "\n"
+    };
+    fs::write(path, contents)
+        .wrap_err_with(|| format!("Could not write plan file {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<PlanEntry> {
+        let mut described = Repository::fixture("octo/old-site");
+        described.description = Some("Old \"site\"".into());
+        described.topics = vec!["web".into()];
+        vec![
+            PlanEntry {
+                repo: described,
+                action: RepoAction::Delete,
+            },
+            PlanEntry {
+                repo: Repository::fixture("octo/keep-but-archive"),
+                action: RepoAction::Archive,
+            },
+        ]
+    }
+
+    fn summary(entries: &[PlanEntry]) -> Vec<(String, Option<String>, Vec<String>, RepoAction)> {
+        entries
+            .iter()
+            .map(|e| {
+                (
+                    e.repo.full_name.clone(),
+                    e.repo.description.clone(),
+                    e.repo.topics.clone(),
+                    e.action,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_json_and_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["plan.json", "plan.toml"] {
+            let path = dir.path().join(name);
+            save(&path, &entries()).unwrap();
+            let loaded = load(&path).unwrap();
+            assert_eq!(summary(&loaded), summary(&entries()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn loads_list_json_output_as_deletions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("repos.json");
+        // What `ghune list --json` prints.
+        let repos = vec![Repository::fixture("octo/a"), Repository::fixture("octo/b")];
+        fs::write(&path, serde_json::to_string_pretty(&repos).unwrap()).unwrap();
+
+        let loaded = load(&path).unwrap();
+        let names: Vec<_> = loaded.iter().map(|e| e.repo.full_name.as_str()).collect();
+        assert_eq!(names, ["octo/a", "octo/b"]);
+        assert!(loaded.iter().all(|e| e.action == RepoAction::Delete));
+    }
+
+    #[test]
+    fn reports_invalid_plans() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plan.toml");
+        fs::write(&path, "[[repos]]\nfull_name = \"octo/a\"\n").unwrap();
+        let err = load(&path).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Invalid plan file {}", path.display())
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::github::types::{parse_timestamp, Repository};
+
+/// A search box query: `key:value` filter tokens plus the free text left
+/// over for fuzzy matching. Tokens that fail to parse are reported in
+/// `errors` and otherwise ignored, so a half-typed filter doesn't empty the
+/// list.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub text: String,
+    pub predicates: Vec<Predicate>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Predicate {
+    Is(RepoKind),
+    Not(Box<Predicate>),
+    Lang(String),
+    Stars(Comparison, u32),
+    Updated(Comparison, DateTime<Utc>),
+    Owner(String),
+    Topic(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepoKind {
+    Fork,
+    Private,
+    Public,
+    Archived,
+    Template,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    /// Split a leading `<`, `<=`, `>`, `>=` or `=` off `value`.
+    fn split(value: &str) -> (Self, &str) {
+        for (prefix, cmp) in [
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+            ("=", Comparison::Equal),
+        ] {
+            if let Some(rest) = value.strip_prefix(prefix) {
+                return (cmp, rest);
+            }
+        }
+        (Comparison::Equal, value)
+    }
+
+    fn holds<T: Ord>(self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+
+    /// The comparison with its sides swapped, so `a < b` becomes `b > a`.
+    fn flip(self) -> Self {
+        match self {
+            Comparison::Less => Comparison::Greater,
+            Comparison::LessOrEqual => Comparison::GreaterOrEqual,
+            Comparison::Equal => Comparison::Equal,
+            Comparison::GreaterOrEqual => Comparison::LessOrEqual,
+            Comparison::Greater => Comparison::Less,
+        }
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let mut query = Query::default();
+        let mut text = Vec::new();
+
+        for token in input.split_whitespace() {
+            let (negated, body) = match token.strip_prefix('-') {
+                Some(rest) if rest.contains(':') => (true, rest),
+                _ => (false, token),
+            };
+            let Some((key, value)) = body.split_once(':') else {
+                text.push(token);
+                continue;
+            };
+
+            match parse_predicate(key, value) {
+                Ok(predicate) if negated => {
+                    query.predicates.push(Predicate::Not(Box::new(predicate)))
+                }
+                Ok(predicate) => query.predicates.push(predicate),
+                Err(e) => query.errors.push(e),
+            }
+        }
+
+        query.text = text.join(" ");
+        query
+    }
+
+    pub fn matches(&self, repo: &Repository) -> bool {
+        self.predicates.iter().all(|p| p.matches(repo))
+    }
+}
+
+impl Predicate {
+    pub fn matches(&self, repo: &Repository) -> bool {
+        match self {
+            Predicate::Is(kind) => match kind {
+                RepoKind::Fork => repo.fork,
+                RepoKind::Private => repo.private,
+                RepoKind::Public => !repo.private,
+                RepoKind::Archived => repo.archived,
+                RepoKind::Template => repo.is_template,
+            },
+            Predicate::Not(inner) => !inner.matches(repo),
+            Predicate::Lang(lang) => repo
+                .language
+                .as_deref()
+                .is_some_and(|l| l.eq_ignore_ascii_case(lang)),
+            Predicate::Stars(cmp, n) => cmp.holds(repo.stargazers_count, *n),
+            Predicate::Updated(cmp, at) => repo
+                .updated_at
+                .as_deref()
+                .and_then(parse_timestamp)
+                .is_some_and(|updated| match cmp {
+                    Comparison::Equal => updated.date_naive() == at.date_naive(),
+                    _ => cmp.holds(updated, *at),
+                }),
+            Predicate::Owner(owner) => repo.owner.eq_ignore_ascii_case(owner),
+            Predicate::Topic(topic) => repo.topics.iter().any(|t| t.eq_ignore_ascii_case(topic)),
+        }
+    }
+}
+
+fn parse_predicate(key: &str, value: &str) -> Result<Predicate, String> {
+    if value.is_empty() {
+        return Err(format!("{}: needs a value", key));
+    }
+
+    match key {
+        "is" => {
+            let kind = match value.to_lowercase().as_str() {
+                "fork" => RepoKind::Fork,
+                "private" => RepoKind::Private,
+                "public" => RepoKind::Public,
+                "archived" => RepoKind::Archived,
+                "template" => RepoKind::Template,
+                _ => return Err(format!("unknown is:{}", value)),
+            };
+            Ok(Predicate::Is(kind))
+        }
+        "lang" => Ok(Predicate::Lang(value.to_string())),
+        "owner" => Ok(Predicate::Owner(value.to_string())),
+        "topic" => Ok(Predicate::Topic(value.to_string())),
+        "stars" => {
+            let (cmp, n) = Comparison::split(value);
+            let n = n
+                .parse()
+                .map_err(|_| format!("stars: expects a number, got '{}'", n))?;
+            Ok(Predicate::Stars(cmp, n))
+        }
+        "updated" => {
+            let (cmp, when) = Comparison::split(value);
+            // A date compares directly; an age like `2y` means "updated
+            // less/more than 2y ago", which is the opposite direction.
+            if let Ok(date) = NaiveDate::parse_from_str(when, "%Y-%m-%d") {
+                let at = date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+                return Ok(Predicate::Updated(cmp, at));
+            }
+            let age = parse_age(when).map_err(|e| format!("updated: {} or a date", e))?;
+            Ok(Predicate::Updated(cmp.flip(), Utc::now() - age))
+        }
+        _ => Err(format!("unknown filter {}:", key)),
+    }
+}
+
+/// Parse an age such as `30d`, `2w`, `6m` or `2y`, which must be positive.
+pub fn parse_age(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("expected an age like 90d, 6m or 2y, got '{}'", s);
+    let unit = s.chars().last().ok_or_else(invalid)?;
+    let n: i64 = s[..s.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    if n <= 0 {
+        return Err(format!("an age must be more than zero, got '{}'", s));
+    }
+    let days_per_unit = match unit {
+        'd' => 1,
+        'w' => 7,
+        'm' => 30,
+        'y' => 365,
+        _ => return Err(invalid()),
+    };
+    n.checked_mul(days_per_unit)
+        .and_then(Duration::try_days)
+        .ok_or_else(|| format!("age '{}' is too large", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_filters_from_text() {
+        let query = Query::parse("is:fork demo lang:Rust -topic:old owner:acme app");
+        assert_eq!(query.text, "demo app");
+        assert_eq!(
+            query.predicates,
+            vec![
+                Predicate::Is(RepoKind::Fork),
+                Predicate::Lang("Rust".into()),
+                Predicate::Not(Box::new(Predicate::Topic("old".into()))),
+                Predicate::Owner("acme".into()),
+            ]
+        );
+        assert!(query.errors.is_empty());
+    }
+
+    #[test]
+    fn parses_star_comparisons() {
+        let query = Query::parse("stars:<5 stars:>=100 stars:7");
+        assert_eq!(
+            query.predicates,
+            vec![
+                Predicate::Stars(Comparison::Less, 5),
+                Predicate::Stars(Comparison::GreaterOrEqual, 100),
+                Predicate::Stars(Comparison::Equal, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn ages_flip_the_comparison_but_dates_do_not() {
+        let query = Query::parse("updated:>2y updated:<2023-01-01");
+        let Predicate::Updated(cmp, at) = &query.predicates[0] else {
+            panic!("{:?}", query.predicates);
+        };
+        // Updated more than 2y ago means updated before now - 2y.
+        assert_eq!(*cmp, Comparison::Less);
+        let expected = Utc::now() - Duration::days(730);
+        assert!((*at - expected).num_seconds().abs() < 5);
+
+        let Predicate::Updated(cmp, at) = &query.predicates[1] else {
+            panic!("{:?}", query.predicates);
+        };
+        assert_eq!(*cmp, Comparison::Less);
+        assert_eq!(
+            at.date_naive(),
+            NaiveDate::from_ymd_opt(2023, 1, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn parses_age_units() {
+        assert_eq!(parse_age("30d"), Ok(Duration::days(30)));
+        assert_eq!(parse_age("2w"), Ok(Duration::days(14)));
+        assert_eq!(parse_age("6m"), Ok(Duration::days(180)));
+        assert_eq!(parse_age("2y"), Ok(Duration::days(730)));
+    }
+
+    #[test]
+    fn rejects_invalid_ages() {
+        for age in ["", "d", "3", "3h", "x3d", "-3d", "0d", "99999999999999999y"] {
+            assert!(parse_age(age).is_err(), "{}", age);
+        }
+    }
+
+    #[test]
+    fn reports_invalid_filters() {
+        let query = Query::parse("is:bogus stars:many updated:<-3d colour:red lang: keep");
+        assert_eq!(query.errors.len(), 5, "{:?}", query.errors);
+        assert!(query.predicates.is_empty());
+        assert_eq!(query.text, "keep");
+    }
+}
@@ -0,0 +1,220 @@
+use serde::Deserialize;
+
+use crate::github::types::{parse_timestamp, RepoDetails, Repository};
+
+/// The `[safety]` section of the config file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SafetyConfig {
+    /// `owner/name` patterns (`*` matches anything) that can never be staged.
+    pub protected: Vec<String>,
+    /// Repos with more stars than this are flagged as risky.
+    pub star_threshold: u32,
+    /// Repos pushed to within this many days are flagged as risky.
+    pub recent_push_days: i64,
+    /// Bulk staging changes touching more repos than this ask first.
+    pub bulk_confirm_threshold: usize,
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            protected: Vec::new(),
+            star_threshold: 25,
+            recent_push_days: 30,
+            bulk_confirm_threshold: 20,
+        }
+    }
+}
+
+/// Something about a repo that makes deleting it more likely to hurt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Risk {
+    Stars(u32),
+    Forks(u32),
+    Watchers(u32),
+    RecentlyPushed(i64),
+    OpenPulls(usize),
+    Template,
+    /// Fetching open pull requests and watchers failed, so those risks
+    /// couldn't be ruled out.
+    Unverified,
+}
+
+impl Risk {
+    pub fn label(&self) -> String {
+        match self {
+            Risk::Stars(n) => format!("★{}", n),
+            Risk::Forks(n) => format!("{} fork{}", n, plural(*n as usize)),
+            Risk::Watchers(n) => format!("{} watcher{}", n, plural(*n as usize)),
+            Risk::RecentlyPushed(0) => "pushed today".to_string(),
+            Risk::RecentlyPushed(days) => format!("pushed {}d ago", days),
+            Risk::OpenPulls(n) => format!("{} open PR{}", n, plural(*n)),
+            Risk::Template => "template".to_string(),
+            Risk::Unverified => "could not verify open PRs/watchers".to_string(),
+        }
+    }
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+impl SafetyConfig {
+    pub fn is_protected(&self, full_name: &str) -> bool {
+        let name = full_name.to_lowercase();
+        self.protected
+            .iter()
+            .any(|pattern| glob_match(&pattern.to_lowercase(), &name))
+    }
+
+    /// Risk flags for `repo`. Open pull requests and watchers are only known
+    /// once `details` have been fetched.
+    pub fn risks(&self, repo: &Repository, details: Option<&RepoDetails>) -> Vec<Risk> {
+        let mut risks = Vec::new();
+        if repo.stargazers_count > self.star_threshold {
+            risks.push(Risk::Stars(repo.stargazers_count));
+        }
+        if repo.forks_count > 0 {
+            risks.push(Risk::Forks(repo.forks_count));
+        }
+        // The owner watches their own repos by default.
+        if let Some(watchers) = details.map(|d| d.subscribers).filter(|&n| n > 1) {
+            risks.push(Risk::Watchers(watchers));
+        }
+        if let Some(pushed) = repo.pushed_at.as_deref().and_then(parse_timestamp) {
+            let days = chrono::Utc::now().signed_duration_since(pushed).num_days();
+            if days < self.recent_push_days {
+                risks.push(Risk::RecentlyPushed(days.max(0)));
+            }
+        }
+        if let Some(pulls) = details.map(|d| d.open_pulls).filter(|&n| n > 0) {
+            risks.push(Risk::OpenPulls(pulls));
+        }
+        if repo.is_template {
+            risks.push(Risk::Template);
+        }
+        risks
+    }
+}
+
+/// Match `text` against `pattern`, where `*` matches any run of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matching() {
+        for (pattern, text, expected) in [
+            ("octo/repo", "octo/repo", true),
+            ("octo/repo", "octo/repo2", false),
+            ("octo/*", "octo/anything", true),
+            ("octo/*", "octo/", true),
+            ("octo/*", "other/repo", false),
+            ("*/dotfiles", "octo/dotfiles", true),
+            ("*/dotfiles", "octo/dotfiles-old", false),
+            ("octo/*-prod", "octo/api-prod", true),
+            ("octo/*-prod", "octo/api-production", false),
+            ("*", "", true),
+            ("a*b*c", "abc", true),
+            ("a*b*c", "axxbyyc", true),
+            ("a*b*c", "acb", false),
+            ("ab*ba", "aba", false),
+        ] {
+            assert_eq!(
+                glob_match(pattern, text),
+                expected,
+                "{} against {}",
+                pattern,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn protection_ignores_case() {
+        let safety = SafetyConfig {
+            protected: vec!["Octo/*".into(), "me/dotfiles".into()],
+            ..SafetyConfig::default()
+        };
+        for (name, expected) in [
+            ("octo/api", true),
+            ("OCTO/API", true),
+            ("me/DotFiles", true),
+            ("me/dotfiles2", false),
+            ("other/api", false),
+        ] {
+            assert_eq!(safety.is_protected(name), expected, "{}", name);
+        }
+    }
+
+    fn days_ago(days: i64) -> String {
+        (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339()
+    }
+
+    /// Adjusts the fixture repo for one case.
+    type Setup = fn(&mut Repository);
+
+    #[test]
+    fn risk_flags() {
+        let safety = SafetyConfig::default();
+        let quiet = RepoDetails {
+            parent: None,
+            subscribers: 1,
+            open_pulls: 0,
+        };
+        let busy = RepoDetails {
+            parent: None,
+            subscribers: 4,
+            open_pulls: 2,
+        };
+        let cases: Vec<(Setup, Option<&RepoDetails>, Vec<Risk>)> = vec![
+            (|_| {}, None, vec![]),
+            (|_| {}, Some(&quiet), vec![]),
+            (
+                |_| {},
+                Some(&busy),
+                vec![Risk::Watchers(4), Risk::OpenPulls(2)],
+            ),
+            (|r| r.stargazers_count = 25, None, vec![]),
+            (|r| r.stargazers_count = 26, None, vec![Risk::Stars(26)]),
+            (|r| r.forks_count = 1, None, vec![Risk::Forks(1)]),
+            (
+                |r| r.pushed_at = Some(days_ago(3)),
+                None,
+                vec![Risk::RecentlyPushed(3)],
+            ),
+            (|r| r.pushed_at = Some(days_ago(30)), None, vec![]),
+            (|r| r.is_template = true, None, vec![Risk::Template]),
+        ];
+        for (i, (setup, details, expected)) in cases.into_iter().enumerate() {
+            let mut repo = Repository::fixture("octo/repo");
+            setup(&mut repo);
+            assert_eq!(safety.risks(&repo, details), expected, "case {}", i);
+        }
+    }
+}
@@ -1,13 +1,29 @@
-use std::collections::HashSet;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
-use crate::github::types::Repository;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::{
+    audit::AuditEntry,
+    executor::JobStatus,
+    filter::RepoFilter,
+    github::{
+        rate_limit::RateLimitTracker,
+        types::{RepoDetails, Repository},
+    },
+    keymap::{KeyAction, Keymap},
+    safety::{Risk, SafetyConfig},
+    tui::theme::Theme,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AppMode {
     Search,
     Staging,
     ConfirmDeletion,
     Deleting,
+    History,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,9 +34,12 @@ pub enum StatusLevel {
     Success,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
 pub enum SortMode {
     #[default]
+    #[value(name = "updated")]
+    #[serde(rename = "updated")]
     LastUpdated,
     Created,
     Name,
@@ -47,6 +66,105 @@ impl SortMode {
     }
 }
 
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoAction {
+    #[default]
+    Delete,
+    Archive,
+    Unarchive,
+}
+
+impl RepoAction {
+    /// The non-destructive alternative to deletion for a repo in the given
+    /// archived state.
+    pub fn archive_toggle(archived: bool) -> Self {
+        if archived {
+            RepoAction::Unarchive
+        } else {
+            RepoAction::Archive
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RepoAction::Delete => "delete",
+            RepoAction::Archive => "archive",
+            RepoAction::Unarchive => "unarchive",
+        }
+    }
+
+    pub fn progress_label(self) -> &'static str {
+        match self {
+            RepoAction::Delete => "Deleting",
+            RepoAction::Archive => "Archiving",
+            RepoAction::Unarchive => "Unarchiving",
+        }
+    }
+
+    pub fn past_tense(self) -> &'static str {
+        match self {
+            RepoAction::Delete => "deleted",
+            RepoAction::Archive => "archived",
+            RepoAction::Unarchive => "unarchived",
+        }
+    }
+}
+
+/// Progress of one staged action while changes are being applied.
+#[derive(Debug, Clone)]
+pub struct JobState {
+    pub repo: String,
+    pub action: RepoAction,
+    pub status: JobStatus,
+}
+
+/// Lazily fetched details for one repo, shown in the detail pane and used
+/// for the risk checks on staged deletions.
+#[derive(Debug, Clone)]
+pub enum DetailsState {
+    Loading,
+    Loaded(RepoDetails),
+    Failed(String),
+}
+
+/// Lazily fetched README for the detail pane; `Loaded(None)` if the repo
+/// has none.
+#[derive(Debug, Clone)]
+pub enum ReadmeState {
+    Loading,
+    Loaded(Option<String>),
+    Failed(String),
+}
+
+/// How long a status message stays up.
+const STATUS_DURATION: Duration = Duration::from_secs(4);
+
+/// Staging changes kept for undo; older ones are dropped.
+const UNDO_LIMIT: usize = 100;
+
+/// One undoable change to the staged set: what it did, and the staged set
+/// to go back to (from before it on the undo stack, after it on redo).
+#[derive(Debug, Clone)]
+pub struct StagingSnapshot {
+    pub description: String,
+    pub staged: HashMap<String, RepoAction>,
+}
+
+/// A staging change to several repos at once, kept while it waits for
+/// confirmation.
+#[derive(Debug, Clone)]
+pub struct BulkChange {
+    /// What the change does, for the prompt and undo.
+    pub description: String,
+    /// New action for each repo, `None` to unstage it.
+    pub changes: Vec<(String, Option<RepoAction>)>,
+    /// Protected repos left out of the change.
+    pub protected: usize,
+}
+
 pub const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
 pub struct AppState {
@@ -54,19 +172,64 @@ pub struct AppState {
     pub repositories: Vec<Repository>,
     pub filtered_indices: Vec<usize>,
     pub selected_index: usize,
-    pub staged_for_deletion: HashSet<String>,
+    pub staged_for_deletion: HashMap<String, RepoAction>,
     pub staged_selected_index: usize,
+    pub undo_stack: Vec<StagingSnapshot>,
+    pub redo_stack: Vec<StagingSnapshot>,
+    /// Bulk change waiting for `y` because it touches many repos.
+    pub pending_bulk: Option<BulkChange>,
+    /// Repo where a visual range selection started.
+    pub visual_anchor: Option<String>,
     pub search_query: String,
     pub status_message: Option<(String, StatusLevel)>,
+    /// When `status_message` was set, for expiring it.
+    pub status_set_at: Instant,
     pub loading: bool,
-    pub deletion_progress: Option<(usize, usize)>,
-    pub deleting_repo: Option<String>,
+    /// The list is from the on-disk cache or an earlier load and is being
+    /// (or failed to be) refreshed.
+    pub repos_stale: bool,
+    /// Repositories received so far by the refresh in progress.
+    pub loaded_count: usize,
+    pub jobs: Vec<JobState>,
+    /// Every job has finished; their results stay up until dismissed.
+    pub jobs_done: bool,
+    pub cancelling: bool,
     pub spinner_frame: usize,
     pub dry_run: bool,
     pub confirmation_input: String,
     pub filter_private: bool,
+    pub filter_public: bool,
     pub filter_forks: bool,
+    pub filter_archived: bool,
+    pub filter_empty: bool,
+    pub stale_after: Option<chrono::Duration>,
     pub sort_mode: SortMode,
+    pub login: Option<String>,
+    /// Token profile in use, shown next to the login.
+    pub profile: String,
+    /// Why the token can't delete anything; forces dry-run mode, in which
+    /// staged deletions are reported as failed.
+    pub token_warning: Option<&'static str>,
+    /// Enterprise host name; `None` on github.com.
+    pub host: Option<String>,
+    pub owner_filter: Option<String>,
+    pub history: Vec<AuditEntry>,
+    pub history_selected_index: usize,
+    pub rate_limit: RateLimitTracker,
+    pub show_details: bool,
+    pub details: HashMap<String, DetailsState>,
+    pub readmes: HashMap<String, ReadmeState>,
+    pub safety: SafetyConfig,
+    /// Risky repos whose deletion has been acknowledged in the open
+    /// confirmation dialog.
+    pub acknowledged: HashSet<String>,
+    pub keymap: Keymap,
+    pub theme: Theme,
+    pub show_help: bool,
+    pub help_scroll: usize,
+    /// Help lines that fit in the overlay as last drawn, which bounds
+    /// `help_scroll`.
+    pub help_visible: Cell<usize>,
 }
 
 impl AppState {
@@ -76,19 +239,49 @@ impl AppState {
             repositories: Vec::new(),
             filtered_indices: Vec::new(),
             selected_index: 0,
-            staged_for_deletion: HashSet::new(),
+            staged_for_deletion: HashMap::new(),
             staged_selected_index: 0,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            pending_bulk: None,
+            visual_anchor: None,
             search_query: String::new(),
             status_message: None,
+            status_set_at: Instant::now(),
             loading: true,
-            deletion_progress: None,
-            deleting_repo: None,
+            repos_stale: false,
+            loaded_count: 0,
+            jobs: Vec::new(),
+            jobs_done: false,
+            cancelling: false,
             spinner_frame: 0,
             dry_run,
             confirmation_input: String::new(),
             filter_private: false,
+            filter_public: false,
             filter_forks: false,
+            filter_archived: false,
+            filter_empty: false,
+            stale_after: None,
             sort_mode: SortMode::default(),
+            login: None,
+            profile: String::new(),
+            token_warning: None,
+            host: None,
+            owner_filter: None,
+            history: Vec::new(),
+            history_selected_index: 0,
+            rate_limit: RateLimitTracker::default(),
+            show_details: false,
+            details: HashMap::new(),
+            readmes: HashMap::new(),
+            safety: SafetyConfig::default(),
+            acknowledged: HashSet::new(),
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            show_help: false,
+            help_scroll: 0,
+            help_visible: Cell::new(0),
         }
     }
 
@@ -96,22 +289,162 @@ impl AppState {
         self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
     }
 
+    /// Advance the spinner and drop a status message once it has been up
+    /// for `STATUS_DURATION`. Messages stay while changes are being applied
+    /// and while a bulk change waits for confirmation.
+    pub fn tick(&mut self) {
+        self.advance_spinner();
+        if self.mode != AppMode::Deleting
+            && self.pending_bulk.is_none()
+            && self.status_set_at.elapsed() >= STATUS_DURATION
+        {
+            self.clear_status();
+        }
+    }
+
     pub fn spinner(&self) -> &'static str {
         SPINNER_FRAMES[self.spinner_frame]
     }
 
+    pub fn repo_filter(&self) -> RepoFilter {
+        RepoFilter {
+            query: self.search_query.clone(),
+            private_only: self.filter_private,
+            public_only: self.filter_public,
+            forks_only: self.filter_forks,
+            archived_only: self.filter_archived,
+            empty_only: self.filter_empty,
+            stale: self.stale_after,
+            owner: self.owner_filter.clone(),
+            sort: self.sort_mode,
+        }
+    }
+
+    /// Set the query, filters and sort from `filter`, e.g. the startup
+    /// defaults from the command line and config file.
+    pub fn set_repo_filter(&mut self, filter: RepoFilter) {
+        self.search_query = filter.query;
+        self.filter_private = filter.private_only;
+        self.filter_public = filter.public_only;
+        self.filter_forks = filter.forks_only;
+        self.filter_archived = filter.archived_only;
+        self.filter_empty = filter.empty_only;
+        self.stale_after = filter.stale;
+        self.owner_filter = filter.owner;
+        self.sort_mode = filter.sort;
+    }
+
     pub fn selected_repo(&self) -> Option<&Repository> {
         self.filtered_indices
             .get(self.selected_index)
             .and_then(|&idx| self.repositories.get(idx))
     }
 
+    /// Move the cursor to `full_name` if it's in the filtered list.
+    pub fn select_repo(&mut self, full_name: &str) {
+        if let Some(pos) = self
+            .filtered_indices
+            .iter()
+            .position(|&i| self.repositories[i].full_name == full_name)
+        {
+            self.selected_index = pos;
+        }
+    }
+
     pub fn staged_repos_sorted(&self) -> Vec<String> {
-        let mut names: Vec<_> = self.staged_for_deletion.iter().cloned().collect();
+        let mut names: Vec<_> = self.staged_for_deletion.keys().cloned().collect();
         names.sort();
         names
     }
 
+    /// Distinct owners of the loaded repositories, with the authenticated
+    /// user first and organizations sorted after it.
+    pub fn owners(&self) -> Vec<String> {
+        let mut owners: Vec<String> = self
+            .repositories
+            .iter()
+            .map(|r| r.owner.clone())
+            .filter(|owner| Some(owner) != self.login.as_ref())
+            .collect();
+        owners.sort_by_key(|owner| owner.to_lowercase());
+        owners.dedup();
+        if let Some(login) = &self.login {
+            owners.insert(0, login.clone());
+        }
+        owners
+    }
+
+    /// Step the owner filter through all owners, then back to showing all.
+    pub fn cycle_owner_filter(&mut self) {
+        let owners = self.owners();
+        let next = match &self.owner_filter {
+            None => 0,
+            Some(current) => match owners.iter().position(|o| o == current) {
+                Some(pos) => pos + 1,
+                None => 0,
+            },
+        };
+        self.owner_filter = owners.get(next).cloned();
+    }
+
+    pub fn jobs_finished(&self) -> usize {
+        self.jobs.iter().filter(|j| j.status.is_finished()).count()
+    }
+
+    pub fn jobs_failed(&self) -> usize {
+        self.jobs
+            .iter()
+            .filter(|j| matches!(j.status, JobStatus::Failed(_)))
+            .count()
+    }
+
+    pub fn staged_action_count(&self, action: RepoAction) -> usize {
+        self.staged_for_deletion
+            .values()
+            .filter(|&&a| a == action)
+            .count()
+    }
+
+    pub fn repo_risks(&self, full_name: &str) -> Vec<Risk> {
+        let Some(repo) = self.repositories.iter().find(|r| r.full_name == full_name) else {
+            return Vec::new();
+        };
+        match self.details.get(full_name) {
+            Some(DetailsState::Loaded(details)) => self.safety.risks(repo, Some(details)),
+            Some(DetailsState::Failed(_)) => {
+                let mut risks = self.safety.risks(repo, None);
+                risks.push(Risk::Unverified);
+                risks
+            }
+            _ => self.safety.risks(repo, None),
+        }
+    }
+
+    /// Staged deletions whose details (open PRs, watchers) are still loading.
+    pub fn pending_risk_checks(&self) -> usize {
+        self.staged_for_deletion
+            .iter()
+            .filter(|&(name, &action)| {
+                action == RepoAction::Delete
+                    && matches!(self.details.get(name), None | Some(DetailsState::Loading))
+            })
+            .count()
+    }
+
+    /// The first staged deletion with risk flags that hasn't been
+    /// acknowledged yet, with its risks.
+    pub fn next_unacknowledged(&self) -> Option<(String, Vec<Risk>)> {
+        self.staged_repos_sorted()
+            .into_iter()
+            .filter(|name| self.staged_for_deletion[name] == RepoAction::Delete)
+            .filter(|name| !self.acknowledged.contains(name))
+            .map(|name| {
+                let risks = self.repo_risks(&name);
+                (name, risks)
+            })
+            .find(|(_, risks)| !risks.is_empty())
+    }
+
     pub fn move_selection(&mut self, delta: i32) {
         match self.mode {
             AppMode::Search => {
@@ -130,38 +463,528 @@ impl AppState {
                 let new_idx = (self.staged_selected_index as i32 + delta).rem_euclid(len);
                 self.staged_selected_index = new_idx as usize;
             }
+            AppMode::History => {
+                if self.history.is_empty() {
+                    return;
+                }
+                let len = self.history.len() as i32;
+                let new_idx = (self.history_selected_index as i32 + delta).rem_euclid(len);
+                self.history_selected_index = new_idx as usize;
+            }
             AppMode::ConfirmDeletion | AppMode::Deleting => {}
         }
     }
 
+    /// Rows of the help overlay for the current mode: every binding from the
+    /// keymap, then keys that aren't bindings, such as typing a query.
+    pub fn help_entries(&self) -> Vec<(String, &'static str)> {
+        let mut entries: Vec<(String, &'static str)> = self
+            .keymap
+            .bindings(self.mode)
+            .iter()
+            .map(|(action, chords)| {
+                let keys: Vec<String> = chords.iter().map(|c| c.to_string()).collect();
+                (keys.join(", "), action.description())
+            })
+            .collect();
+        match self.mode {
+            AppMode::Search => entries.push((
+                "other keys".to_string(),
+                "Type a query, e.g. api is:fork stars:<5",
+            )),
+            AppMode::ConfirmDeletion => {
+                entries.push(("0-9".to_string(), "Type the number of staged repos"))
+            }
+            AppMode::Staging | AppMode::Deleting | AppMode::History => {}
+        }
+        entries
+    }
+
+    pub fn scroll_help(&mut self, delta: i32) {
+        // Scrolling stops once the last page is full, as the overlay draws it.
+        let max = self
+            .help_entries()
+            .len()
+            .saturating_sub(self.help_visible.get()) as i32;
+        self.help_scroll = (self.help_scroll as i32 + delta).clamp(0, max) as usize;
+    }
+
+    /// Jump to the first or last item of the current mode's list.
+    pub fn select_edge(&mut self, last: bool) {
+        let (index, len) = match self.mode {
+            AppMode::Search => (&mut self.selected_index, self.filtered_indices.len()),
+            AppMode::Staging => (
+                &mut self.staged_selected_index,
+                self.staged_for_deletion.len(),
+            ),
+            AppMode::History => (&mut self.history_selected_index, self.history.len()),
+            AppMode::ConfirmDeletion | AppMode::Deleting => return,
+        };
+        *index = if last { len.saturating_sub(1) } else { 0 };
+    }
+
     pub fn toggle_stage(&mut self) {
+        self.toggle_stage_with(|_| RepoAction::Delete);
+    }
+
+    pub fn toggle_stage_archive(&mut self) {
+        self.toggle_stage_with(|repo| RepoAction::archive_toggle(repo.archived));
+    }
+
+    fn toggle_stage_with(&mut self, action: impl Fn(&Repository) -> RepoAction) {
         if let Some(repo) = self.selected_repo() {
             let name = repo.full_name.clone();
-            if self.staged_for_deletion.contains(&name) {
-                self.staged_for_deletion.remove(&name);
-            } else {
-                self.staged_for_deletion.insert(name);
+            let action = action(repo);
+            let before = self.staged_for_deletion.clone();
+            if self.staged_for_deletion.remove(&name).is_some() {
+                self.record_staging(format!("unstage {}", name), before);
+                return;
+            }
+            if self.safety.is_protected(&name) {
+                self.set_status(format!("{} is protected", name), StatusLevel::Warning);
+                return;
             }
+            let description = format!("stage {} to {}", name, action.label());
+            self.staged_for_deletion.insert(name, action);
+            self.record_staging(description, before);
+        }
+    }
+
+    /// Switch the selected staged repo between deletion and (un)archiving.
+    pub fn cycle_selected_action(&mut self) {
+        let names = self.staged_repos_sorted();
+        let Some(name) = names.get(self.staged_selected_index) else {
+            return;
+        };
+        let archived = self
+            .repositories
+            .iter()
+            .find(|r| &r.full_name == name)
+            .is_some_and(|r| r.archived);
+        let before = self.staged_for_deletion.clone();
+        if let Some(action) = self.staged_for_deletion.get_mut(name) {
+            *action = match action {
+                RepoAction::Delete => RepoAction::archive_toggle(archived),
+                RepoAction::Archive | RepoAction::Unarchive => RepoAction::Delete,
+            };
+            let description = format!("switch {} to {}", name, action.label());
+            self.record_staging(description, before);
         }
     }
 
     pub fn unstage_selected(&mut self) {
         let names = self.staged_repos_sorted();
         if let Some(name) = names.get(self.staged_selected_index) {
+            let before = self.staged_for_deletion.clone();
             self.staged_for_deletion.remove(name);
-            if self.staged_selected_index > 0
-                && self.staged_selected_index >= self.staged_for_deletion.len()
-            {
-                self.staged_selected_index = self.staged_for_deletion.len().saturating_sub(1);
+            self.record_staging(format!("unstage {}", name), before);
+            self.clamp_staged_selection();
+        }
+    }
+
+    pub fn unstage_all(&mut self) {
+        let count = self.staged_for_deletion.len();
+        let before = std::mem::take(&mut self.staged_for_deletion);
+        self.record_staging(format!("unstage all {} repos", count), before);
+        self.staged_selected_index = 0;
+    }
+
+    /// Stage every repo matching the current filter for deletion.
+    pub fn stage_visible(&mut self) {
+        let all = 0..self.filtered_indices.len();
+        let mut change = self.bulk_change(all, |_, staged| {
+            staged.is_none().then_some(Some(RepoAction::Delete))
+        });
+        change.description = format!("stage {} visible repos", change.changes.len());
+        self.request_bulk(change);
+    }
+
+    pub fn unstage_visible(&mut self) {
+        let all = 0..self.filtered_indices.len();
+        let mut change = self.bulk_change(all, |_, staged| staged.is_some().then_some(None));
+        change.description = format!("unstage {} visible repos", change.changes.len());
+        self.request_bulk(change);
+    }
+
+    /// Unstage the visible repos that are staged and stage the rest for
+    /// deletion.
+    pub fn invert_visible(&mut self) {
+        let all = 0..self.filtered_indices.len();
+        let mut change = self.bulk_change(all, |_, staged| match staged {
+            Some(_) => Some(None),
+            None => Some(Some(RepoAction::Delete)),
+        });
+        change.description = format!("invert staging of {} visible repos", change.changes.len());
+        self.request_bulk(change);
+    }
+
+    /// Start a range selection at the cursor, or cancel the one in progress.
+    pub fn toggle_visual(&mut self) {
+        if self.visual_anchor.take().is_some() {
+            self.set_status("Range selection cancelled".to_string(), StatusLevel::Info);
+            return;
+        }
+        if let Some(repo) = self.selected_repo() {
+            self.visual_anchor = Some(repo.full_name.clone());
+            self.set_status(
+                "Range selection: move to extend it, then stage".to_string(),
+                StatusLevel::Info,
+            );
+        }
+    }
+
+    /// Positions in `filtered_indices` covered by the range selection, or
+    /// `None` if there isn't one or its anchor has been filtered out.
+    pub fn visual_range(&self) -> Option<(usize, usize)> {
+        let anchor = self.visual_anchor.as_ref()?;
+        let start = self
+            .filtered_indices
+            .iter()
+            .position(|&i| &self.repositories[i].full_name == anchor)?;
+        Some((
+            start.min(self.selected_index),
+            start.max(self.selected_index),
+        ))
+    }
+
+    /// Stage every repo in the range selection, leaving ones already staged
+    /// as they are, and end the selection.
+    pub fn stage_visual(&mut self, archive: bool) {
+        let Some((start, end)) = self.visual_range() else {
+            self.visual_anchor = None;
+            return;
+        };
+        self.visual_anchor = None;
+        let mut change = self.bulk_change(start..end + 1, |repo, staged| {
+            staged.is_none().then(|| {
+                Some(if archive {
+                    RepoAction::archive_toggle(repo.archived)
+                } else {
+                    RepoAction::Delete
+                })
+            })
+        });
+        change.description = if archive {
+            format!(
+                "stage {} repos in range for archiving",
+                change.changes.len()
+            )
+        } else {
+            format!("stage {} repos in range", change.changes.len())
+        };
+        self.request_bulk(change);
+    }
+
+    /// Changes for the repos at `positions` in the filtered list. `action`
+    /// gets each repo and how it's staged, and returns `None` to leave it
+    /// alone or its new staging; protected repos are never staged.
+    fn bulk_change(
+        &self,
+        positions: std::ops::Range<usize>,
+        action: impl Fn(&Repository, Option<RepoAction>) -> Option<Option<RepoAction>>,
+    ) -> BulkChange {
+        let mut changes = Vec::new();
+        let mut protected = 0;
+        for &i in &self.filtered_indices[positions] {
+            let repo = &self.repositories[i];
+            let staged = self.staged_for_deletion.get(&repo.full_name).copied();
+            let Some(action) = action(repo, staged) else {
+                continue;
+            };
+            if action.is_some() && self.safety.is_protected(&repo.full_name) {
+                protected += 1;
+            } else {
+                changes.push((repo.full_name.clone(), action));
             }
         }
+        BulkChange {
+            description: String::new(),
+            changes,
+            protected,
+        }
+    }
+
+    /// Apply `change`, or hold it for confirmation if it touches more repos
+    /// than `safety.bulk_confirm_threshold`.
+    fn request_bulk(&mut self, change: BulkChange) {
+        if change.changes.is_empty() {
+            let message = if change.protected > 0 {
+                format!("Nothing to change ({} protected)", change.protected)
+            } else {
+                "Nothing to change".to_string()
+            };
+            self.set_status(message, StatusLevel::Info);
+            return;
+        }
+        if change.changes.len() > self.safety.bulk_confirm_threshold {
+            let key = self
+                .keymap
+                .hint(AppMode::ConfirmDeletion, KeyAction::Acknowledge)
+                .unwrap_or_default();
+            self.set_status(
+                format!("Really {}? [{}/N]", change.description, key),
+                StatusLevel::Warning,
+            );
+            self.pending_bulk = Some(change);
+            return;
+        }
+        self.apply_bulk(change);
+    }
+
+    pub fn apply_bulk(&mut self, change: BulkChange) {
+        let before = self.staged_for_deletion.clone();
+        for (name, action) in change.changes {
+            match action {
+                Some(action) => self.staged_for_deletion.insert(name, action),
+                None => self.staged_for_deletion.remove(&name),
+            };
+        }
+        let mut message = format!("Done: {}", change.description);
+        if change.protected > 0 {
+            message.push_str(&format!(" ({} protected skipped)", change.protected));
+        }
+        self.record_staging(change.description, before);
+        self.set_status(message, StatusLevel::Success);
+    }
+
+    /// Remember the staged set from before a change so it can be undone.
+    /// Changes that left the set as it was aren't recorded.
+    pub fn record_staging(&mut self, description: String, before: HashMap<String, RepoAction>) {
+        if before == self.staged_for_deletion {
+            return;
+        }
+        if self.undo_stack.len() == UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(StagingSnapshot {
+            description,
+            staged: before,
+        });
+        self.redo_stack.clear();
+    }
+
+    pub fn undo_staging(&mut self) {
+        let Some(snapshot) = self.undo_stack.pop() else {
+            self.set_status("Nothing to undo".to_string(), StatusLevel::Warning);
+            return;
+        };
+        let after = std::mem::replace(&mut self.staged_for_deletion, snapshot.staged);
+        self.set_status(
+            format!("Undid: {}", snapshot.description),
+            StatusLevel::Info,
+        );
+        self.redo_stack.push(StagingSnapshot {
+            description: snapshot.description,
+            staged: after,
+        });
+        self.clamp_staged_selection();
+    }
+
+    pub fn redo_staging(&mut self) {
+        let Some(snapshot) = self.redo_stack.pop() else {
+            self.set_status("Nothing to redo".to_string(), StatusLevel::Warning);
+            return;
+        };
+        let before = std::mem::replace(&mut self.staged_for_deletion, snapshot.staged);
+        self.set_status(
+            format!("Redid: {}", snapshot.description),
+            StatusLevel::Info,
+        );
+        self.undo_stack.push(StagingSnapshot {
+            description: snapshot.description,
+            staged: before,
+        });
+        self.clamp_staged_selection();
+    }
+
+    /// Forget undo history, once the staged changes have been applied and
+    /// going back to them would stage repos that no longer exist.
+    pub fn clear_staging_history(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    fn clamp_staged_selection(&mut self) {
+        if self.staged_selected_index >= self.staged_for_deletion.len() {
+            self.staged_selected_index = self.staged_for_deletion.len().saturating_sub(1);
+        }
     }
 
     pub fn set_status(&mut self, message: String, level: StatusLevel) {
         self.status_message = Some((message, level));
+        self.status_set_at = Instant::now();
     }
 
     pub fn clear_status(&mut self) {
         self.status_message = None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A state listing `names`, all visible, with the cursor on the first.
+    fn state_with(names: &[&str]) -> AppState {
+        let mut state = AppState::new(true);
+        state.repositories = names.iter().map(|name| Repository::fixture(name)).collect();
+        state.filtered_indices = (0..names.len()).collect();
+        state
+    }
+
+    fn staged(state: &AppState) -> Vec<String> {
+        state.staged_repos_sorted()
+    }
+
+    #[test]
+    fn undo_and_redo_a_toggle() {
+        let mut state = state_with(&["octo/a"]);
+        state.toggle_stage();
+        assert_eq!(staged(&state), ["octo/a"]);
+
+        state.undo_staging();
+        assert!(staged(&state).is_empty());
+        assert_eq!(
+            state.status_message.as_ref().unwrap().0,
+            "Undid: stage octo/a to delete"
+        );
+
+        state.redo_staging();
+        assert_eq!(staged(&state), ["octo/a"]);
+        assert_eq!(
+            state.status_message.as_ref().unwrap().0,
+            "Redid: stage octo/a to delete"
+        );
+        assert_eq!(state.undo_stack.len(), 1);
+        assert!(state.redo_stack.is_empty());
+    }
+
+    #[test]
+    fn new_change_clears_redo() {
+        let mut state = state_with(&["octo/a", "octo/b"]);
+        state.toggle_stage();
+        state.undo_staging();
+        assert_eq!(state.redo_stack.len(), 1);
+
+        state.selected_index = 1;
+        state.toggle_stage();
+        assert!(state.redo_stack.is_empty());
+        state.redo_staging();
+        assert_eq!(staged(&state), ["octo/b"]);
+    }
+
+    #[test]
+    fn undo_message_outlives_ticks() {
+        let mut state = state_with(&["octo/a"]);
+        state.toggle_stage();
+        state.undo_staging();
+        state.tick();
+        assert_eq!(
+            state.status_message.as_ref().unwrap().0,
+            "Undid: stage octo/a to delete"
+        );
+
+        state.status_set_at -= STATUS_DURATION;
+        state.tick();
+        assert!(state.status_message.is_none());
+    }
+
+    #[test]
+    fn unchanged_set_is_not_recorded() {
+        let mut state = state_with(&["octo/a"]);
+        state.safety.protected = vec!["octo/*".into()];
+        state.toggle_stage();
+        assert!(state.undo_stack.is_empty());
+        state.undo_staging();
+        assert_eq!(state.status_message.as_ref().unwrap().0, "Nothing to undo");
+    }
+
+    #[test]
+    fn undo_history_is_bounded() {
+        let mut state = state_with(&["octo/a"]);
+        for _ in 0..UNDO_LIMIT + 1 {
+            state.toggle_stage();
+        }
+        assert_eq!(state.undo_stack.len(), UNDO_LIMIT);
+        // The oldest change, staging octo/a, was dropped.
+        assert_eq!(state.undo_stack[0].description, "unstage octo/a");
+        for _ in 0..UNDO_LIMIT {
+            state.undo_staging();
+        }
+        assert_eq!(staged(&state), ["octo/a"]);
+        state.undo_staging();
+        assert_eq!(state.status_message.as_ref().unwrap().0, "Nothing to undo");
+    }
+
+    #[test]
+    fn unstage_all_is_one_step() {
+        let mut state = state_with(&["octo/a", "octo/b"]);
+        state.toggle_stage();
+        state.selected_index = 1;
+        state.toggle_stage();
+        state.unstage_all();
+        assert!(staged(&state).is_empty());
+
+        state.undo_staging();
+        assert_eq!(staged(&state), ["octo/a", "octo/b"]);
+    }
+
+    #[test]
+    fn stage_visible_skips_protected_in_one_step() {
+        let mut state = state_with(&["octo/a", "octo/b", "keep/c"]);
+        state.safety.protected = vec!["keep/*".into()];
+        state.stage_visible();
+        assert_eq!(staged(&state), ["octo/a", "octo/b"]);
+        assert_eq!(
+            state.status_message.as_ref().unwrap().0,
+            "Done: stage 2 visible repos (1 protected skipped)"
+        );
+        assert_eq!(state.undo_stack.len(), 1);
+
+        state.undo_staging();
+        assert!(staged(&state).is_empty());
+    }
+
+    #[test]
+    fn invert_visible_swaps_staging() {
+        let mut state = state_with(&["octo/a", "octo/b"]);
+        state.toggle_stage();
+        state.invert_visible();
+        assert_eq!(staged(&state), ["octo/b"]);
+        state.undo_staging();
+        assert_eq!(staged(&state), ["octo/a"]);
+    }
+
+    #[test]
+    fn visual_range_stages_between_cursors() {
+        let mut state = state_with(&["octo/a", "octo/b", "keep/c", "octo/d", "octo/e"]);
+        state.safety.protected = vec!["keep/*".into()];
+        state.selected_index = 3;
+        state.toggle_visual();
+        state.selected_index = 1;
+        assert_eq!(state.visual_range(), Some((1, 3)));
+
+        state.stage_visual(false);
+        assert_eq!(staged(&state), ["octo/b", "octo/d"]);
+        assert!(state.visual_anchor.is_none());
+        assert_eq!(state.undo_stack.len(), 1);
+        assert_eq!(state.undo_stack[0].description, "stage 2 repos in range");
+
+        state.undo_staging();
+        assert!(staged(&state).is_empty());
+    }
+
+    #[test]
+    fn large_bulk_change_waits_for_confirmation() {
+        let mut state = state_with(&["octo/a", "octo/b", "octo/c"]);
+        state.safety.bulk_confirm_threshold = 2;
+        state.stage_visible();
+        assert!(staged(&state).is_empty());
+        assert!(state.undo_stack.is_empty());
+
+        let change = state.pending_bulk.take().unwrap();
+        state.apply_bulk(change);
+        assert_eq!(staged(&state), ["octo/a", "octo/b", "octo/c"]);
+        assert_eq!(state.undo_stack.len(), 1);
+    }
+}
@@ -5,22 +5,42 @@ use crossterm::event::{Event as CrosstermEvent, KeyEvent};
 use futures::StreamExt;
 use tokio::sync::mpsc;
 
+use crate::{
+    executor::JobStatus,
+    github::{
+        client::RepoListing,
+        types::{RepoDetails, Repository},
+    },
+};
+
 #[derive(Debug, Clone)]
 pub enum Event {
     Key(KeyEvent),
     Tick,
     Resize,
+    /// A background job (by index into `AppState::jobs`) changed status.
+    Job(usize, JobStatus),
+    /// Every background job has finished or been cancelled.
+    JobsFinished,
+    /// Another page of repositories arrived during a refresh.
+    ReposPage(Vec<Repository>),
+    /// A refresh of the repository list finished.
+    Repos(Result<RepoListing, String>),
+    /// Details for a repo finished loading.
+    Details(String, Result<RepoDetails, String>),
+    /// A repo's README finished loading.
+    Readme(String, Result<Option<String>, String>),
 }
 
 pub struct EventHandler {
     rx: mpsc::UnboundedReceiver<Event>,
-    _tx: mpsc::UnboundedSender<Event>,
+    tx: mpsc::UnboundedSender<Event>,
 }
 
 impl EventHandler {
     pub fn new(tick_rate: Duration) -> Self {
         let (tx, rx) = mpsc::unbounded_channel();
-        let _tx = tx.clone();
+        let sender = tx.clone();
 
         tokio::spawn(async move {
             let mut reader = crossterm::event::EventStream::new();
@@ -31,18 +51,13 @@ impl EventHandler {
                     maybe_event = reader.next() => {
                         match maybe_event {
                             Some(Ok(evt)) => {
-                                match evt {
-                                    CrosstermEvent::Key(key) => {
-                                        if tx.send(Event::Key(key)).is_err() {
-                                            break;
-                                        }
-                                    }
-                                    CrosstermEvent::Resize(_, _) => {
-                                        if tx.send(Event::Resize).is_err() {
-                                            break;
-                                        }
-                                    }
-                                    _ => {}
+                                let event = match evt {
+                                    CrosstermEvent::Key(key) => Event::Key(key),
+                                    CrosstermEvent::Resize(_, _) => Event::Resize,
+                                    _ => continue,
+                                };
+                                if tx.send(event).is_err() {
+                                    break;
                                 }
                             }
                             Some(Err(_)) => break,
@@ -58,7 +73,12 @@ impl EventHandler {
             }
         });
 
-        Self { rx, _tx }
+        Self { rx, tx: sender }
+    }
+
+    /// A handle for background tasks to feed events into the main loop.
+    pub fn sender(&self) -> mpsc::UnboundedSender<Event> {
+        self.tx.clone()
     }
 
     pub async fn next(&mut self) -> Result<Event> {
@@ -1,5 +1,6 @@
 pub mod event;
 pub mod terminal;
+pub mod theme;
 pub mod ui;
 pub mod widgets;
 
@@ -0,0 +1,63 @@
+use std::str::FromStr;
+
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+
+/// Colours used by every widget, by role. Set from the `[theme]` section of
+/// the config file with names (`"cyan"`, `"dark gray"`), 256-colour indices
+/// or `"#rrggbb"`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Key hints, links and the detail pane.
+    #[serde(deserialize_with = "color")]
+    pub accent: Color,
+    /// Search mode and successful outcomes.
+    #[serde(deserialize_with = "color")]
+    pub success: Color,
+    /// Staging mode, warnings and risk flags.
+    #[serde(deserialize_with = "color")]
+    pub warning: Color,
+    /// Deletions and errors.
+    #[serde(deserialize_with = "color")]
+    pub danger: Color,
+    /// Archiving and the history view.
+    #[serde(deserialize_with = "color")]
+    pub info: Color,
+    /// Active filters and the dry-run marker.
+    #[serde(deserialize_with = "color")]
+    pub highlight: Color,
+    #[serde(deserialize_with = "color")]
+    pub text: Color,
+    /// Secondary text such as descriptions.
+    #[serde(deserialize_with = "color")]
+    pub subtle: Color,
+    /// Inactive borders, timestamps and hints.
+    #[serde(deserialize_with = "color")]
+    pub muted: Color,
+    /// Text on a selected row.
+    #[serde(deserialize_with = "color")]
+    pub selection: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            accent: Color::Cyan,
+            success: Color::Green,
+            warning: Color::Yellow,
+            danger: Color::Red,
+            info: Color::Blue,
+            highlight: Color::Magenta,
+            text: Color::White,
+            subtle: Color::Gray,
+            muted: Color::DarkGray,
+            selection: Color::Black,
+        }
+    }
+}
+
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    Color::from_str(&s).map_err(|_| serde::de::Error::custom(format!("unknown colour '{}'", s)))
+}
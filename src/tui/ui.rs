@@ -1,12 +1,13 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     Frame,
 };
 
-use crate::state::AppState;
+use crate::state::{AppMode, AppState};
 
 use super::widgets::{
-    render_header, render_repo_list, render_search, render_staged, render_status,
+    render_details, render_header, render_help, render_history, render_jobs, render_repo_list,
+    render_search, render_staged, render_status,
 };
 
 pub fn render(frame: &mut Frame, state: &AppState) {
@@ -20,11 +21,24 @@ pub fn render(frame: &mut Frame, state: &AppState) {
         .split(frame.area());
 
     render_header(frame, main_chunks[0], state);
+    render_status(frame, main_chunks[2], state);
+
+    if state.mode == AppMode::History {
+        render_history(frame, main_chunks[1], state);
+    } else {
+        render_content(frame, main_chunks[1], state);
+    }
 
+    if state.show_help {
+        render_help(frame, frame.area(), state);
+    }
+}
+
+fn render_content(frame: &mut Frame, area: Rect, state: &AppState) {
     let content_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
-        .split(main_chunks[1]);
+        .split(area);
 
     let left_chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -33,6 +47,16 @@ pub fn render(frame: &mut Frame, state: &AppState) {
 
     render_search(frame, left_chunks[0], state);
     render_repo_list(frame, left_chunks[1], state);
-    render_staged(frame, content_chunks[1], state);
-    render_status(frame, main_chunks[2], state);
+    if state.mode == AppMode::Deleting {
+        render_jobs(frame, content_chunks[1], state);
+    } else if state.show_details {
+        let right_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+            .split(content_chunks[1]);
+        render_staged(frame, right_chunks[0], state);
+        render_details(frame, right_chunks[1], state);
+    } else {
+        render_staged(frame, content_chunks[1], state);
+    }
 }
@@ -0,0 +1,163 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    keymap::KeyAction,
+    state::{AppMode, AppState, DetailsState, ReadmeState},
+    tui::theme::Theme,
+};
+
+use super::repo_list::format_relative_time;
+
+/// How many README lines fit in the preview before it is cut off.
+const README_PREVIEW_LINES: usize = 20;
+
+fn format_size(kb: u32) -> String {
+    if kb >= 1024 * 1024 {
+        format!("{:.1} GB", kb as f64 / (1024.0 * 1024.0))
+    } else if kb >= 1024 {
+        format!("{:.1} MB", kb as f64 / 1024.0)
+    } else {
+        format!("{} KB", kb)
+    }
+}
+
+fn field<'a>(theme: &Theme, label: &'a str, value: String) -> Line<'a> {
+    Line::from(vec![
+        Span::styled(format!("{:<10}", label), Style::default().fg(theme.muted)),
+        Span::styled(value, Style::default().fg(theme.text)),
+    ])
+}
+
+pub fn render_details(frame: &mut Frame, area: Rect, state: &AppState) {
+    let key = state
+        .keymap
+        .hint(AppMode::Search, KeyAction::ToggleDetails)
+        .map(|k| format!("[{}] ", k))
+        .unwrap_or_default();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(state.theme.muted))
+        .title(Span::styled(
+            format!(" {}Details ", key),
+            Style::default().fg(state.theme.accent),
+        ));
+
+    let Some(repo) = state.selected_repo() else {
+        let empty = Paragraph::new(Line::from(Span::styled(
+            "No repository selected",
+            Style::default().fg(state.theme.muted),
+        )))
+        .block(block);
+        frame.render_widget(empty, area);
+        return;
+    };
+
+    let details = state.details.get(&repo.full_name);
+    let loaded = match details {
+        Some(DetailsState::Loaded(details)) => Some(details),
+        _ => None,
+    };
+
+    let mut lines = vec![Line::from(Span::styled(
+        repo.full_name.as_str(),
+        Style::default()
+            .fg(state.theme.accent)
+            .add_modifier(Modifier::BOLD),
+    ))];
+    if let Some(desc) = &repo.description {
+        lines.push(Line::from(Span::styled(
+            desc.as_str(),
+            Style::default().fg(state.theme.subtle),
+        )));
+    }
+    lines.push(Line::raw(""));
+
+    if let Some(language) = &repo.language {
+        lines.push(field(&state.theme, "Language", language.clone()));
+    }
+    lines.push(field(&state.theme, "Size", format_size(repo.size_kb)));
+    if let Some(branch) = &repo.default_branch {
+        lines.push(field(&state.theme, "Branch", branch.clone()));
+    }
+
+    // GitHub counts pull requests as issues, so split them out once the
+    // pull request count is known.
+    match loaded {
+        Some(details) => {
+            let issues = (repo.open_issues_count as usize).saturating_sub(details.open_pulls);
+            lines.push(field(&state.theme, "Issues", issues.to_string()));
+            lines.push(field(&state.theme, "PRs", details.open_pulls.to_string()));
+        }
+        None => lines.push(field(
+            &state.theme,
+            "Issues",
+            repo.open_issues_count.to_string(),
+        )),
+    }
+
+    lines.push(field(
+        &state.theme,
+        "Stars",
+        format!("{}  forks {}", repo.stargazers_count, repo.forks_count),
+    ));
+    let pushed = format_relative_time(&repo.pushed_at);
+    if !pushed.is_empty() {
+        lines.push(field(&state.theme, "Pushed", format!("{} ago", pushed)));
+    }
+    if !repo.topics.is_empty() {
+        lines.push(field(&state.theme, "Topics", repo.topics.join(", ")));
+    }
+    if let Some(details) = loaded {
+        lines.push(field(
+            &state.theme,
+            "Watchers",
+            details.subscribers.to_string(),
+        ));
+    }
+    if let Some(parent) = loaded.and_then(|d| d.parent.as_ref()) {
+        lines.push(field(&state.theme, "Fork of", parent.clone()));
+    }
+    lines.push(Line::raw(""));
+
+    if let Some(DetailsState::Failed(e)) = details {
+        lines.push(Line::from(Span::styled(
+            format!("Failed to load details: {}", e),
+            Style::default().fg(state.theme.danger),
+        )));
+    }
+    match state.readmes.get(&repo.full_name) {
+        None | Some(ReadmeState::Loading) => lines.push(Line::from(Span::styled(
+            format!("{} Loading README...", state.spinner()),
+            Style::default().fg(state.theme.muted),
+        ))),
+        Some(ReadmeState::Failed(e)) => lines.push(Line::from(Span::styled(
+            format!("Failed to load README: {}", e),
+            Style::default().fg(state.theme.danger),
+        ))),
+        Some(ReadmeState::Loaded(readme)) => match readme {
+            None => lines.push(Line::from(Span::styled(
+                "No README",
+                Style::default().fg(state.theme.muted),
+            ))),
+            Some(readme) => {
+                lines.extend(
+                    readme
+                        .lines()
+                        .take(README_PREVIEW_LINES)
+                        .map(|line| Line::raw(line.to_string())),
+                );
+            }
+        },
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, area);
+}
@@ -1,22 +1,31 @@
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::Paragraph,
     Frame,
 };
 
-use crate::state::{AppMode, AppState};
+use crate::{
+    auth::DEFAULT_PROFILE,
+    keymap::KeyAction,
+    state::{AppMode, AppState},
+};
 
 pub fn render_header(frame: &mut Frame, area: Rect, state: &AppState) {
     let mode_style = match state.mode {
         AppMode::Search => Style::default()
-            .fg(Color::Green)
+            .fg(state.theme.success)
             .add_modifier(Modifier::BOLD),
         AppMode::Staging | AppMode::ConfirmDeletion => Style::default()
-            .fg(Color::Yellow)
+            .fg(state.theme.warning)
+            .add_modifier(Modifier::BOLD),
+        AppMode::Deleting => Style::default()
+            .fg(state.theme.danger)
+            .add_modifier(Modifier::BOLD),
+        AppMode::History => Style::default()
+            .fg(state.theme.info)
             .add_modifier(Modifier::BOLD),
-        AppMode::Deleting => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
     };
 
     let mode_text = match state.mode {
@@ -24,10 +33,22 @@ pub fn render_header(frame: &mut Frame, area: Rect, state: &AppState) {
         AppMode::Staging => "STAGING",
         AppMode::ConfirmDeletion => "CONFIRM",
         AppMode::Deleting => "DELETING",
+        AppMode::History => "HISTORY",
     };
 
-    let repo_count = if state.loading {
-        "Loading...".to_string()
+    let repo_count = if state.repos_stale {
+        let refreshing = if state.loading {
+            format!(", refreshing {} {}", state.loaded_count, state.spinner())
+        } else {
+            String::new()
+        };
+        format!("Repos: {} (stale{})", state.repositories.len(), refreshing)
+    } else if state.loading {
+        format!(
+            "Repos: {} (loading {})",
+            state.repositories.len(),
+            state.spinner()
+        )
     } else {
         format!("Repos: {}", state.repositories.len())
     };
@@ -36,25 +57,105 @@ pub fn render_header(frame: &mut Frame, area: Rect, state: &AppState) {
         Span::styled(
             " [DRY RUN]",
             Style::default()
-                .fg(Color::Magenta)
+                .fg(state.theme.highlight)
                 .add_modifier(Modifier::BOLD),
         )
     } else {
         Span::raw("")
     };
 
+    let owner = state.owner_filter.as_deref().unwrap_or("all");
+    let owner_style = if state.owner_filter.is_some() {
+        Style::default()
+            .fg(state.theme.highlight)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(state.theme.subtle)
+    };
+
+    let rate_limit = if let Some(wait) = state.rate_limit.backoff_remaining() {
+        Span::styled(
+            format!("  Rate limited, retrying in {}s", wait.as_secs() + 1),
+            Style::default()
+                .fg(state.theme.danger)
+                .add_modifier(Modifier::BOLD),
+        )
+    } else if let Some(limit) = state.rate_limit.current() {
+        let color = if limit.remaining * 20 < limit.limit {
+            state.theme.danger
+        } else if limit.remaining * 5 < limit.limit {
+            state.theme.warning
+        } else {
+            state.theme.muted
+        };
+        Span::styled(
+            format!(
+                "  API: {}/{} (resets {}m)",
+                limit.remaining,
+                limit.limit,
+                limit.resets_in().as_secs().div_ceil(60)
+            ),
+            Style::default().fg(color),
+        )
+    } else {
+        Span::raw("")
+    };
+
+    let token_warning = match state.token_warning {
+        Some(warning) => Span::styled(
+            format!("  ⚠ {}: dry run only", warning),
+            Style::default()
+                .fg(state.theme.danger)
+                .add_modifier(Modifier::BOLD),
+        ),
+        None => Span::raw(""),
+    };
+
+    let account = match &state.login {
+        Some(login) => format!("@{}", login),
+        None => "@…".to_string(),
+    };
+    let mut profile = if state.profile == DEFAULT_PROFILE {
+        String::new()
+    } else {
+        format!(" ({})", state.profile)
+    };
+    if let Some(host) = &state.host {
+        profile.push_str(&format!(" on {}", host));
+    }
+
+    let owner_key = state
+        .keymap
+        .hint(AppMode::Search, KeyAction::CycleOwner)
+        .map(|k| format!(" ({})", k))
+        .unwrap_or_default();
+
     let line = Line::from(vec![
         Span::styled(
             "ghune",
             Style::default()
-                .fg(Color::Cyan)
+                .fg(state.theme.accent)
                 .add_modifier(Modifier::BOLD),
         ),
         dry_run_indicator,
         Span::raw("  "),
+        Span::styled(
+            account,
+            Style::default()
+                .fg(state.theme.text)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(profile, Style::default().fg(state.theme.subtle)),
+        token_warning,
+        Span::raw("  "),
         Span::styled(format!("[{}]", mode_text), mode_style),
         Span::raw("  "),
-        Span::styled(repo_count, Style::default().fg(Color::Gray)),
+        Span::styled(repo_count, Style::default().fg(state.theme.subtle)),
+        Span::raw("  "),
+        Span::styled("Owner: ", Style::default().fg(state.theme.muted)),
+        Span::styled(owner, owner_style),
+        Span::styled(owner_key, Style::default().fg(state.theme.muted)),
+        rate_limit,
     ]);
 
     let header = Paragraph::new(line);
@@ -0,0 +1,87 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::state::{AppMode, AppState};
+
+fn mode_name(mode: AppMode) -> &'static str {
+    match mode {
+        AppMode::Search => "Search",
+        AppMode::Staging => "Staging",
+        AppMode::ConfirmDeletion => "Confirm",
+        AppMode::Deleting => "Applying",
+        AppMode::History => "History",
+    }
+}
+
+/// Every binding of the current mode, straight from the active keymap.
+pub fn render_help(frame: &mut Frame, area: Rect, state: &AppState) {
+    let entries = state.help_entries();
+    let key_width = entries
+        .iter()
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let lines: Vec<Line> = entries
+        .iter()
+        .map(|(keys, description)| {
+            Line::from(vec![
+                Span::styled(
+                    format!(" {:<width$}  ", keys, width = key_width),
+                    Style::default()
+                        .fg(state.theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(*description, Style::default().fg(state.theme.text)),
+            ])
+        })
+        .collect();
+
+    let width = area.width.saturating_sub(4).min(72);
+    let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+    let dialog_area = Rect::new(
+        area.x + area.width.saturating_sub(width) / 2,
+        area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    );
+
+    // Keep the last page full rather than scrolling past the end.
+    let visible = height.saturating_sub(2) as usize;
+    state.help_visible.set(visible);
+    let scroll = state.help_scroll.min(lines.len().saturating_sub(visible));
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(state.theme.accent))
+        .title(Span::styled(
+            format!(" Keys: {} ", mode_name(state.mode)),
+            Style::default()
+                .fg(state.theme.accent)
+                .add_modifier(Modifier::BOLD),
+        ));
+    if lines.len() > visible {
+        block = block.title_bottom(Span::styled(
+            format!(
+                " {}-{} of {} (↑/↓ to scroll) ",
+                scroll + 1,
+                (scroll + visible).min(lines.len()),
+                lines.len()
+            ),
+            Style::default().fg(state.theme.muted),
+        ));
+    }
+
+    frame.render_widget(Clear, dialog_area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((scroll as u16, 0)),
+        dialog_area,
+    );
+}
@@ -0,0 +1,98 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::state::AppState;
+
+fn format_timestamp(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|dt| {
+            dt.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+pub fn render_history(frame: &mut Frame, area: Rect, state: &AppState) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(state.theme.info))
+        .title(Span::styled(
+            format!(" History ({}) ", state.history.len()),
+            Style::default()
+                .fg(state.theme.info)
+                .add_modifier(Modifier::BOLD),
+        ));
+
+    let items: Vec<ListItem> = state
+        .history
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| {
+            let is_selected = idx == state.history_selected_index;
+
+            let (marker, marker_style) = if entry.success {
+                ("✓ ", Style::default().fg(state.theme.success))
+            } else {
+                ("✗ ", Style::default().fg(state.theme.danger))
+            };
+
+            let name_style = if is_selected {
+                Style::default()
+                    .fg(state.theme.selection)
+                    .bg(state.theme.info)
+            } else {
+                Style::default().fg(state.theme.text)
+            };
+
+            let mut spans = vec![
+                Span::styled(marker, marker_style),
+                Span::styled(
+                    format_timestamp(&entry.timestamp),
+                    Style::default().fg(state.theme.muted),
+                ),
+                Span::raw(" "),
+                Span::styled(
+                    format!("{:<9}", entry.action.label()),
+                    Style::default().fg(state.theme.warning),
+                ),
+                Span::styled(entry.repo.as_str(), name_style),
+            ];
+
+            if let Some(user) = &entry.user {
+                spans.push(Span::styled(
+                    format!(" by {}", user),
+                    Style::default().fg(state.theme.muted),
+                ));
+            }
+            if entry.dry_run {
+                spans.push(Span::styled(
+                    " [DRY RUN]",
+                    Style::default().fg(state.theme.highlight),
+                ));
+            }
+            if let Some(error) = &entry.error {
+                spans.push(Span::styled(
+                    format!(" {}", error),
+                    Style::default().fg(state.theme.danger),
+                ));
+            }
+
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items).block(block);
+
+    let mut list_state = ListState::default();
+    if !state.history.is_empty() {
+        list_state.select(Some(state.history_selected_index));
+    }
+
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
@@ -0,0 +1,97 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Gauge, List, ListItem},
+    Frame,
+};
+
+use crate::{executor::JobStatus, state::AppState};
+
+pub fn render_jobs(frame: &mut Frame, area: Rect, state: &AppState) {
+    let total = state.jobs.len();
+    let finished = state.jobs_finished();
+
+    let title = if state.jobs_done {
+        format!(" Done ({}/{}) ", finished, total)
+    } else if state.cancelling {
+        " Cancelling... ".to_string()
+    } else {
+        format!(" Applying ({}/{}) ", finished, total)
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(state.theme.danger))
+        .title(Span::styled(
+            title,
+            Style::default()
+                .fg(state.theme.danger)
+                .add_modifier(Modifier::BOLD),
+        ));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(1)])
+        .split(inner);
+
+    let ratio = if total == 0 {
+        1.0
+    } else {
+        finished as f64 / total as f64
+    };
+    let gauge = Gauge::default()
+        .gauge_style(
+            Style::default()
+                .fg(state.theme.danger)
+                .bg(state.theme.muted),
+        )
+        .ratio(ratio)
+        .label(format!("{}/{}", finished, total));
+    frame.render_widget(gauge, chunks[0]);
+
+    // Keep in-flight and failed jobs visible by listing them before the rest.
+    let mut jobs: Vec<_> = state.jobs.iter().collect();
+    jobs.sort_by_key(|job| match job.status {
+        JobStatus::BackingUp | JobStatus::Running => 0,
+        JobStatus::Failed(_) => 1,
+        JobStatus::Queued => 2,
+        JobStatus::Succeeded | JobStatus::Cancelled => 3,
+    });
+
+    let items: Vec<ListItem> = jobs
+        .into_iter()
+        .map(|job| {
+            let (marker, label, style) = match &job.status {
+                JobStatus::Queued => ("·", "queued".to_string(), state.theme.muted),
+                JobStatus::BackingUp => (
+                    state.spinner(),
+                    "backing up".to_string(),
+                    state.theme.warning,
+                ),
+                JobStatus::Running => (
+                    state.spinner(),
+                    job.action.progress_label().to_lowercase(),
+                    state.theme.warning,
+                ),
+                JobStatus::Succeeded => (
+                    "✓",
+                    job.action.past_tense().to_string(),
+                    state.theme.success,
+                ),
+                JobStatus::Failed(e) => ("✗", e.clone(), state.theme.danger),
+                JobStatus::Cancelled => ("-", "cancelled".to_string(), state.theme.muted),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", marker), Style::default().fg(style)),
+                Span::styled(job.repo.as_str(), Style::default().fg(state.theme.text)),
+                Span::styled(format!(" {}", label), Style::default().fg(style)),
+            ]))
+        })
+        .collect();
+
+    frame.render_widget(List::new(items), chunks[1]);
+}
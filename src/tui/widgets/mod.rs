@@ -1,10 +1,18 @@
+mod details;
 mod header;
+mod help;
+mod history;
+mod jobs;
 mod repo_list;
 mod search;
 mod staged;
 mod status;
 
+pub use details::render_details;
 pub use header::render_header;
+pub use help::render_help;
+pub use history::render_history;
+pub use jobs::render_jobs;
 pub use repo_list::render_repo_list;
 pub use search::render_search;
 pub use staged::render_staged;
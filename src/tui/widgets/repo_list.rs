@@ -1,19 +1,23 @@
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, ListState},
     Frame,
 };
 
-use crate::state::{AppMode, AppState};
+use crate::{
+    github::types::parse_timestamp,
+    keymap::KeyAction,
+    state::{AppMode, AppState, RepoAction},
+};
 
-fn format_relative_time(updated_at: &Option<String>) -> String {
+pub(super) fn format_relative_time(updated_at: &Option<String>) -> String {
     let Some(date_str) = updated_at else {
         return String::new();
     };
 
-    let Ok(dt) = chrono::DateTime::parse_from_rfc3339(date_str) else {
+    let Some(dt) = parse_timestamp(date_str) else {
         return String::new();
     };
 
@@ -41,21 +45,29 @@ pub fn render_repo_list(frame: &mut Frame, area: Rect, state: &AppState) {
     let is_active = state.mode == AppMode::Search;
 
     let border_style = if is_active {
-        Style::default().fg(Color::Green)
+        Style::default().fg(state.theme.success)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(state.theme.muted)
     };
 
     let filtered_count = state.filtered_indices.len();
     let total_count = state.repositories.len();
 
-    let tab_hint = if !is_active { "[Tab] " } else { "" };
-    let title = if state.search_query.is_empty() {
-        format!(" {}Repositories ({}) ", tab_hint, total_count)
+    let tab_hint = match state.keymap.hint(AppMode::Staging, KeyAction::Back) {
+        Some(key) if !is_active => format!("[{}] ", key),
+        _ => String::new(),
+    };
+    let owner = state
+        .owner_filter
+        .as_ref()
+        .map(|o| format!(" · {}", o))
+        .unwrap_or_default();
+    let title = if filtered_count == total_count {
+        format!(" {}Repositories{} ({}) ", tab_hint, owner, total_count)
     } else {
         format!(
-            " {}Repositories ({}/{}) ",
-            tab_hint, filtered_count, total_count
+            " {}Repositories{} ({}/{}) ",
+            tab_hint, owner, filtered_count, total_count
         )
     };
 
@@ -66,13 +78,15 @@ pub fn render_repo_list(frame: &mut Frame, area: Rect, state: &AppState) {
             title,
             if is_active {
                 Style::default()
-                    .fg(Color::Green)
+                    .fg(state.theme.success)
                     .add_modifier(Modifier::BOLD)
             } else {
-                Style::default().fg(Color::DarkGray)
+                Style::default().fg(state.theme.muted)
             },
         ));
 
+    let visual_range = state.visual_range().filter(|_| is_active);
+
     let items: Vec<ListItem> = state
         .filtered_indices
         .iter()
@@ -80,25 +94,51 @@ pub fn render_repo_list(frame: &mut Frame, area: Rect, state: &AppState) {
         .map(|(display_idx, &repo_idx)| {
             let repo = &state.repositories[repo_idx];
             let is_selected = display_idx == state.selected_index && is_active;
-            let is_staged = state.staged_for_deletion.contains(&repo.full_name);
+            let in_range =
+                visual_range.is_some_and(|(start, end)| (start..=end).contains(&display_idx));
+            let staged_action = state.staged_for_deletion.get(&repo.full_name);
 
             let mut spans = Vec::new();
 
-            if is_staged {
-                spans.push(Span::styled("● ", Style::default().fg(Color::Red)));
+            let staged_color = match staged_action {
+                Some(RepoAction::Delete) => state.theme.danger,
+                Some(RepoAction::Archive | RepoAction::Unarchive) => state.theme.info,
+                None => state.theme.text,
+            };
+
+            if staged_action.is_some() {
+                spans.push(Span::styled("● ", Style::default().fg(staged_color)));
             } else {
                 spans.push(Span::raw("  "));
             }
 
             let name_style = if is_selected {
-                Style::default().fg(Color::Black).bg(Color::Green)
-            } else if is_staged {
-                Style::default().fg(Color::Red)
+                Style::default()
+                    .fg(state.theme.selection)
+                    .bg(state.theme.success)
+            } else if in_range {
+                Style::default()
+                    .fg(state.theme.selection)
+                    .bg(state.theme.highlight)
+            } else if staged_action.is_some() {
+                Style::default().fg(staged_color)
             } else {
-                Style::default().fg(Color::White)
+                Style::default().fg(state.theme.text)
             };
 
-            spans.push(Span::styled(&repo.full_name, name_style));
+            let owner_style = if is_selected || in_range {
+                name_style
+            } else {
+                Style::default().fg(state.theme.muted)
+            };
+            let name = repo
+                .full_name
+                .strip_prefix(&format!("{}/", repo.owner))
+                .unwrap_or(&repo.full_name);
+            if name.len() != repo.full_name.len() {
+                spans.push(Span::styled(format!("{}/", repo.owner), owner_style));
+            }
+            spans.push(Span::styled(name, name_style));
             spans.push(Span::raw(" "));
 
             if repo.private {
@@ -107,11 +147,17 @@ pub fn render_repo_list(frame: &mut Frame, area: Rect, state: &AppState) {
             if repo.fork {
                 spans.push(Span::styled("🍴", Style::default()));
             }
+            if repo.archived {
+                spans.push(Span::styled("📦", Style::default()));
+            }
+            if state.safety.is_protected(&repo.full_name) {
+                spans.push(Span::styled("🛡", Style::default()));
+            }
 
             if repo.stargazers_count > 0 {
                 spans.push(Span::styled(
                     format!(" ★{}", repo.stargazers_count),
-                    Style::default().fg(Color::Yellow),
+                    Style::default().fg(state.theme.warning),
                 ));
             }
 
@@ -119,7 +165,7 @@ pub fn render_repo_list(frame: &mut Frame, area: Rect, state: &AppState) {
             if !relative_time.is_empty() {
                 spans.push(Span::styled(
                     format!(" {}", relative_time),
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(state.theme.muted),
                 ));
             }
 
@@ -1,20 +1,27 @@
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
 
-use crate::state::{AppMode, AppState};
+use crate::{
+    query::Query,
+    state::{AppMode, AppState},
+};
 
 pub fn render_search(frame: &mut Frame, area: Rect, state: &AppState) {
     let is_active = state.mode == AppMode::Search;
 
-    let border_style = if is_active {
-        Style::default().fg(Color::Green)
+    let errors = Query::parse(&state.search_query).errors;
+
+    let border_style = if !errors.is_empty() {
+        Style::default().fg(state.theme.danger)
+    } else if is_active {
+        Style::default().fg(state.theme.success)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(state.theme.muted)
     };
 
     let block = Block::default()
@@ -24,22 +31,30 @@ pub fn render_search(frame: &mut Frame, area: Rect, state: &AppState) {
             " Search ",
             if is_active {
                 Style::default()
-                    .fg(Color::Green)
+                    .fg(state.theme.success)
                     .add_modifier(Modifier::BOLD)
             } else {
-                Style::default().fg(Color::DarkGray)
+                Style::default().fg(state.theme.muted)
             },
         ));
 
+    let block = match errors.first() {
+        Some(err) => block.title_bottom(Span::styled(
+            format!(" {} ", err),
+            Style::default().fg(state.theme.danger),
+        )),
+        None => block,
+    };
+
     let cursor_char = if is_active { "_" } else { "" };
 
     let content = Line::from(vec![
-        Span::styled("> ", Style::default().fg(Color::Yellow)),
+        Span::styled("> ", Style::default().fg(state.theme.warning)),
         Span::raw(&state.search_query),
         Span::styled(
             cursor_char,
             Style::default()
-                .fg(Color::White)
+                .fg(state.theme.text)
                 .add_modifier(Modifier::SLOW_BLINK),
         ),
     ]);
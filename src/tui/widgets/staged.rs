@@ -6,19 +6,26 @@ use ratatui::{
     Frame,
 };
 
-use crate::state::{AppMode, AppState};
+use crate::{
+    keymap::KeyAction,
+    state::{AppMode, AppState, RepoAction},
+    tui::theme::Theme,
+};
 
 pub fn render_staged(frame: &mut Frame, area: Rect, state: &AppState) {
     let is_active = state.mode == AppMode::Staging || state.mode == AppMode::ConfirmDeletion;
 
     let border_style = if is_active {
-        Style::default().fg(Color::Yellow)
+        Style::default().fg(state.theme.warning)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(state.theme.muted)
     };
 
     let staged_count = state.staged_for_deletion.len();
-    let tab_hint = if !is_active { "[Tab] " } else { "" };
+    let tab_hint = match state.keymap.hint(AppMode::Search, KeyAction::FocusStaging) {
+        Some(key) if !is_active => format!("[{}] ", key),
+        _ => String::new(),
+    };
     let title = format!(" {}Staged ({}) ", tab_hint, staged_count);
 
     let block = Block::default()
@@ -28,12 +35,12 @@ pub fn render_staged(frame: &mut Frame, area: Rect, state: &AppState) {
             title,
             if is_active {
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(state.theme.warning)
                     .add_modifier(Modifier::BOLD)
             } else if staged_count > 0 {
-                Style::default().fg(Color::Red)
+                Style::default().fg(state.theme.danger)
             } else {
-                Style::default().fg(Color::DarkGray)
+                Style::default().fg(state.theme.muted)
             },
         ));
 
@@ -46,18 +53,30 @@ pub fn render_staged(frame: &mut Frame, area: Rect, state: &AppState) {
             let is_selected = idx == state.staged_selected_index && state.mode == AppMode::Staging;
 
             let repo = state.repositories.iter().find(|r| &r.full_name == name);
+            let action = state
+                .staged_for_deletion
+                .get(name)
+                .copied()
+                .unwrap_or_default();
+            let action_color = action_color(&state.theme, action);
 
             let mut spans = Vec::new();
 
-            spans.push(Span::styled("■ ", Style::default().fg(Color::Red)));
+            spans.push(Span::styled("■ ", Style::default().fg(action_color)));
 
             let name_style = if is_selected {
-                Style::default().fg(Color::Black).bg(Color::Yellow)
+                Style::default()
+                    .fg(state.theme.selection)
+                    .bg(state.theme.warning)
             } else {
-                Style::default().fg(Color::Red)
+                Style::default().fg(action_color)
             };
 
             spans.push(Span::styled(name.as_str(), name_style));
+            spans.push(Span::styled(
+                format!(" [{}]", action.label()),
+                Style::default().fg(action_color),
+            ));
 
             if let Some(repo) = repo {
                 spans.push(Span::raw(" "));
@@ -69,6 +88,15 @@ pub fn render_staged(frame: &mut Frame, area: Rect, state: &AppState) {
                 }
             }
 
+            let risks = state.repo_risks(name);
+            if !risks.is_empty() {
+                let labels: Vec<String> = risks.iter().map(|risk| risk.label()).collect();
+                spans.push(Span::styled(
+                    format!(" ⚠ {}", labels.join(", ")),
+                    Style::default().fg(state.theme.warning),
+                ));
+            }
+
             ListItem::new(Line::from(spans))
         })
         .collect();
@@ -87,9 +115,16 @@ pub fn render_staged(frame: &mut Frame, area: Rect, state: &AppState) {
     }
 }
 
+fn action_color(theme: &Theme, action: RepoAction) -> Color {
+    match action {
+        RepoAction::Delete => theme.danger,
+        RepoAction::Archive | RepoAction::Unarchive => theme.info,
+    }
+}
+
 fn render_confirmation_dialog(frame: &mut Frame, area: Rect, state: &AppState) {
-    let dialog_width = 45u16;
-    let dialog_height = 7u16;
+    let dialog_width = 56u16;
+    let dialog_height = 10u16;
 
     let x = area.x + area.width.saturating_sub(dialog_width) / 2;
     let y = area.y + area.height.saturating_sub(dialog_height) / 2;
@@ -105,10 +140,12 @@ fn render_confirmation_dialog(frame: &mut Frame, area: Rect, state: &AppState) {
     let count = state.staged_for_deletion.len();
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Red))
+        .border_style(Style::default().fg(state.theme.danger))
         .title(Span::styled(
-            " Confirm Deletion ",
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            " Confirm Changes ",
+            Style::default()
+                .fg(state.theme.danger)
+                .add_modifier(Modifier::BOLD),
         ));
 
     let inner = block.inner(dialog_area);
@@ -120,39 +157,121 @@ fn render_confirmation_dialog(frame: &mut Frame, area: Rect, state: &AppState) {
             Constraint::Length(1),
             Constraint::Length(1),
             Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
         ])
         .split(inner);
 
-    let prompt = Line::from(vec![
-        Span::raw("Type "),
-        Span::styled(
-            count.to_string(),
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-        ),
-        Span::raw(" to delete "),
-        Span::styled(
-            format!("{} repo{}", count, if count == 1 { "" } else { "s" }),
-            Style::default().fg(Color::Red),
-        ),
-        Span::raw(":"),
-    ]);
-    frame.render_widget(Paragraph::new(prompt), chunks[0]);
-
-    let input_line = Line::from(vec![
-        Span::raw("> "),
-        Span::styled(
-            &state.confirmation_input,
+    let mut plan_spans = Vec::new();
+    for action in [
+        RepoAction::Delete,
+        RepoAction::Archive,
+        RepoAction::Unarchive,
+    ] {
+        let n = state.staged_action_count(action);
+        if n == 0 {
+            continue;
+        }
+        if !plan_spans.is_empty() {
+            plan_spans.push(Span::raw(", "));
+        }
+        plan_spans.push(Span::styled(
+            format!("{} {}", action.label(), n),
             Style::default()
-                .fg(Color::White)
+                .fg(action_color(&state.theme, action))
                 .add_modifier(Modifier::BOLD),
-        ),
-        Span::styled("_", Style::default().fg(Color::Gray)),
-    ]);
-    frame.render_widget(Paragraph::new(input_line), chunks[1]);
+        ));
+    }
+    frame.render_widget(Paragraph::new(Line::from(plan_spans)), chunks[0]);
 
+    let pending = state.pending_risk_checks();
+    if pending > 0 {
+        let checking = Line::from(Span::styled(
+            format!(
+                "{} Checking {} repos for risks...",
+                state.spinner(),
+                pending
+            ),
+            Style::default().fg(state.theme.muted),
+        ));
+        frame.render_widget(Paragraph::new(checking), chunks[2]);
+    } else if let Some((name, risks)) = state.next_unacknowledged() {
+        let labels: Vec<String> = risks.iter().map(|risk| risk.label()).collect();
+        let warning = Line::from(vec![
+            Span::styled("⚠ ", Style::default().fg(state.theme.warning)),
+            Span::styled(
+                name,
+                Style::default()
+                    .fg(state.theme.warning)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(warning), chunks[1]);
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                labels.join(", "),
+                Style::default().fg(state.theme.warning),
+            )),
+            chunks[2],
+        );
+        let ack_key = state
+            .keymap
+            .hint(AppMode::ConfirmDeletion, KeyAction::Acknowledge)
+            .unwrap_or_default();
+        let ack = Line::from(vec![
+            Span::raw("Press "),
+            Span::styled(
+                ack_key,
+                Style::default()
+                    .fg(state.theme.warning)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" to delete it anyway"),
+        ]);
+        frame.render_widget(Paragraph::new(ack), chunks[3]);
+    } else {
+        let prompt = Line::from(vec![
+            Span::raw("Type "),
+            Span::styled(
+                count.to_string(),
+                Style::default()
+                    .fg(state.theme.danger)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" to apply to "),
+            Span::styled(
+                format!("{} repo{}", count, if count == 1 { "" } else { "s" }),
+                Style::default().fg(state.theme.danger),
+            ),
+            Span::raw(":"),
+        ]);
+        frame.render_widget(Paragraph::new(prompt), chunks[2]);
+
+        let input_line = Line::from(vec![
+            Span::raw("> "),
+            Span::styled(
+                &state.confirmation_input,
+                Style::default()
+                    .fg(state.theme.text)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("_", Style::default().fg(state.theme.subtle)),
+        ]);
+        frame.render_widget(Paragraph::new(input_line), chunks[3]);
+    }
+
+    let budget = match state.rate_limit.current() {
+        Some(limit) => format!(" · ~{} of {} API calls left", count, limit.remaining),
+        None => String::new(),
+    };
+    let cancel = match state.keymap.hint(AppMode::ConfirmDeletion, KeyAction::Back) {
+        Some(key) => format!("Press {} to cancel", key),
+        None => String::new(),
+    };
     let hint = Line::from(Span::styled(
-        "Press Esc to cancel",
-        Style::default().fg(Color::DarkGray),
+        format!("{}{}", cancel, budget),
+        Style::default().fg(state.theme.muted),
     ));
-    frame.render_widget(Paragraph::new(hint), chunks[2]);
+    frame.render_widget(Paragraph::new(hint), chunks[5]);
 }
@@ -1,68 +1,123 @@
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::Paragraph,
     Frame,
 };
 
-use crate::state::{AppMode, AppState, SortMode, StatusLevel};
+use crate::{
+    executor::JobStatus,
+    keymap::KeyAction,
+    state::{AppMode, AppState, SortMode, StatusLevel},
+};
 
 pub fn render_status(frame: &mut Frame, area: Rect, state: &AppState) {
-    let keybindings = match state.mode {
-        AppMode::Search => vec![
-            ("C-j/k", "Nav"),
-            ("Enter", "Stage"),
-            ("Tab", "Staging"),
-            ("C-p", "Private"),
-            ("C-f", "Forks"),
-            ("C-s", "Sort"),
-            ("C-c", "Quit"),
-        ],
-        AppMode::Staging => vec![
-            ("C-j/C-k", "Navigate"),
-            ("Backspace", "Unstage"),
-            ("Enter", "Delete"),
-            ("Tab/Esc", "Back"),
-            ("C-c/q", "Quit"),
-        ],
-        AppMode::ConfirmDeletion => vec![("Enter", "Confirm"), ("Esc", "Cancel"), ("C-c", "Quit")],
-        AppMode::Deleting => vec![("", "Deleting repositories...")],
+    let mut keybindings: Vec<(String, &str)> = match state.mode {
+        AppMode::Search => hints(
+            state,
+            &[
+                (&[KeyAction::Down, KeyAction::Up], "Nav"),
+                (&[KeyAction::Stage], "Stage"),
+                (&[KeyAction::StageArchive], "Archive"),
+                (&[KeyAction::VisualRange], "Range"),
+                (&[KeyAction::FocusStaging], "Staging"),
+                (&[KeyAction::TogglePrivate], "Private"),
+                (&[KeyAction::ToggleForks], "Forks"),
+                (&[KeyAction::CycleOwner], "Owner"),
+                (&[KeyAction::CycleSort], "Sort"),
+                (&[KeyAction::OpenHistory], "History"),
+                (&[KeyAction::ToggleDetails], "Details"),
+                (&[KeyAction::Quit], "Quit"),
+            ],
+        ),
+        AppMode::Staging => hints(
+            state,
+            &[
+                (&[KeyAction::Down, KeyAction::Up], "Navigate"),
+                (&[KeyAction::Unstage], "Unstage"),
+                (&[KeyAction::Undo], "Undo"),
+                (&[KeyAction::CycleAction], "Delete/Archive"),
+                (&[KeyAction::WritePlan], "Write plan"),
+                (&[KeyAction::Apply], "Apply"),
+                (&[KeyAction::Back], "Back"),
+                (&[KeyAction::Quit], "Quit"),
+            ],
+        ),
+        AppMode::ConfirmDeletion => hints(
+            state,
+            &[
+                (&[KeyAction::Acknowledge], "Acknowledge risk"),
+                (&[KeyAction::Apply], "Confirm"),
+                (&[KeyAction::Back], "Cancel"),
+                (&[KeyAction::Quit], "Quit"),
+            ],
+        ),
+        AppMode::Deleting if state.jobs_done => hints(
+            state,
+            &[(&[KeyAction::Back, KeyAction::CancelJobs], "Close")],
+        ),
+        AppMode::Deleting => hints(state, &[(&[KeyAction::CancelJobs], "Cancel remaining")]),
+        AppMode::History => hints(
+            state,
+            &[
+                (&[KeyAction::Down, KeyAction::Up], "Navigate"),
+                (&[KeyAction::Back], "Back"),
+                (&[KeyAction::Quit], "Quit"),
+            ],
+        ),
     };
 
+    keybindings.extend(hints(state, &[(&[KeyAction::Help], "Help")]));
+
     let mut spans: Vec<Span> = Vec::new();
     for (i, (key, action)) in keybindings.iter().enumerate() {
         if i > 0 {
             spans.push(Span::raw(" │ "));
         }
         spans.push(Span::styled(
-            *key,
+            key.as_str(),
             Style::default()
-                .fg(Color::Cyan)
+                .fg(state.theme.accent)
                 .add_modifier(Modifier::BOLD),
         ));
         spans.push(Span::raw(": "));
-        spans.push(Span::styled(*action, Style::default().fg(Color::Gray)));
+        spans.push(Span::styled(
+            *action,
+            Style::default().fg(state.theme.subtle),
+        ));
     }
 
     // Add active filters/sort indicators
     if state.mode == AppMode::Search {
         let mut filters = Vec::new();
         if state.filter_private {
-            filters.push("🔒");
+            filters.push("🔒".to_string());
+        }
+        if state.filter_public {
+            filters.push("public".to_string());
         }
         if state.filter_forks {
-            filters.push("🍴");
+            filters.push("🍴".to_string());
+        }
+        if state.filter_archived {
+            filters.push("📦".to_string());
+        }
+        if state.filter_empty {
+            filters.push("empty".to_string());
+        }
+        if let Some(stale) = state.stale_after {
+            filters.push(format!("stale {}d", stale.num_days()));
         }
         if state.sort_mode != SortMode::LastUpdated {
-            filters.push(state.sort_mode.label());
+            filters.push(state.sort_mode.label().to_string());
         }
         if !filters.is_empty() {
             spans.push(Span::raw("  "));
             spans.push(Span::styled(
                 format!("[{}]", filters.join(" ")),
                 Style::default()
-                    .fg(Color::Magenta)
+                    .fg(state.theme.highlight)
                     .add_modifier(Modifier::BOLD),
             ));
         }
@@ -70,34 +125,51 @@ pub fn render_status(frame: &mut Frame, area: Rect, state: &AppState) {
 
     let keybindings_line = Line::from(spans);
 
-    let status_line = if let Some((current, total)) = state.deletion_progress {
+    let status_line = if state.mode == AppMode::Deleting && !state.cancelling && !state.jobs_done {
         let spinner = state.spinner();
-        let repo_name = state.deleting_repo.as_deref().unwrap_or("");
+        let in_flight: Vec<&str> = state
+            .jobs
+            .iter()
+            .filter(|job| matches!(job.status, JobStatus::BackingUp | JobStatus::Running))
+            .map(|job| job.repo.as_str())
+            .collect();
+        let failed = state.jobs_failed();
+        let failed_note = if failed > 0 {
+            format!(" ({} failed)", failed)
+        } else {
+            String::new()
+        };
         let dry_run_prefix = if state.dry_run { "[DRY RUN] " } else { "" };
         Line::from(vec![
             Span::styled(
                 format!("{} ", spinner),
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(state.theme.warning)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(
-                format!("{}Deleting {}/{}: ", dry_run_prefix, current, total),
-                Style::default().fg(Color::Yellow),
+                format!(
+                    "{}Applying {}/{}{}: ",
+                    dry_run_prefix,
+                    state.jobs_finished(),
+                    state.jobs.len(),
+                    failed_note
+                ),
+                Style::default().fg(state.theme.warning),
             ),
             Span::styled(
-                repo_name,
+                in_flight.join(", "),
                 Style::default()
-                    .fg(Color::White)
+                    .fg(state.theme.text)
                     .add_modifier(Modifier::BOLD),
             ),
         ])
     } else if let Some((ref msg, ref level)) = state.status_message {
         let style = match level {
-            StatusLevel::Info => Style::default().fg(Color::Blue),
-            StatusLevel::Warning => Style::default().fg(Color::Yellow),
-            StatusLevel::Error => Style::default().fg(Color::Red),
-            StatusLevel::Success => Style::default().fg(Color::Green),
+            StatusLevel::Info => Style::default().fg(state.theme.info),
+            StatusLevel::Warning => Style::default().fg(state.theme.warning),
+            StatusLevel::Error => Style::default().fg(state.theme.danger),
+            StatusLevel::Success => Style::default().fg(state.theme.success),
         };
         Line::from(Span::styled(msg.as_str(), style))
     } else {
@@ -107,3 +179,19 @@ pub fn render_status(frame: &mut Frame, area: Rect, state: &AppState) {
     let paragraph = Paragraph::new(vec![keybindings_line, status_line]);
     frame.render_widget(paragraph, area);
 }
+
+/// Key hints for the current mode from its active bindings: the first key
+/// of each action, joined with `/` when a hint covers several actions.
+fn hints<'a>(state: &AppState, entries: &[(&[KeyAction], &'a str)]) -> Vec<(String, &'a str)> {
+    entries
+        .iter()
+        .filter_map(|&(actions, label)| {
+            let keys: Vec<String> = actions
+                .iter()
+                .filter_map(|&action| state.keymap.keys(state.mode, action).first())
+                .map(|chord| chord.to_string())
+                .collect();
+            (!keys.is_empty()).then(|| (keys.join("/"), label))
+        })
+        .collect()
+}